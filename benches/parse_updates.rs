@@ -0,0 +1,63 @@
+//! Throughput of `parse_updates` compared with deserialising the ticker messages into owned `String`s
+//! first, as it was done before. Run with `cargo bench`.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde::Deserialize;
+
+#[path = "../src/utils.rs"]
+#[allow(unused, clippy::all)]     // linted as part of the binary
+mod utils;
+use utils::*;
+
+/// Number of tickers in a message, like the busiest ones of the !ticker@arr stream
+const N_TICKERS: usize = 500;
+
+/// A single update item with owned strings, the old way
+#[derive(Deserialize)]
+struct OwnedUpdate {
+    #[serde(alias = "E")] ts: u64,
+    #[serde(alias = "s")] symbol: String,
+    #[serde(alias = "x")] px_24h: String,
+    #[serde(alias = "c")] px: String,
+    #[serde(alias = "o")] open: String,
+    #[serde(alias = "h")] high: String,
+    #[serde(alias = "l")] low: String,
+    #[serde(alias = "v")] volume: String,
+    #[serde(alias = "q")] quote_volume: String,
+    #[serde(alias = "n")] n_trades: u64,
+    #[serde(alias = "b")] bid: String,
+    #[serde(alias = "a")] ask: String,
+}
+
+/// Deserialise into `OwnedUpdate`s, then convert them and push them into `out`
+fn parse_owned(s: &str, out: &mut Vec<Update>) -> Result<(), Box<dyn std::error::Error>> {
+    let updates: Vec<OwnedUpdate> = serde_json::from_str(s)?;
+    for u in updates.iter() {
+        out.push(Update { symbol: Symbol::from(u.symbol.as_str()), ts: u.ts, px: parse_dec(&u.px),
+                          px_24h: parse_dec(&u.px_24h), open: parse_dec(&u.open), high: parse_dec(&u.high),
+                          low: parse_dec(&u.low), volume: parse_dec(&u.volume), quote_volume: parse_dec(&u.quote_volume),
+                          n_trades: u.n_trades, bid: parse_dec(&u.bid), ask: parse_dec(&u.ask) });
+    }
+    Ok(())
+}
+
+/// A `!ticker@arr` message with `N_TICKERS` tickers
+fn message() -> String {
+    let tickers: Vec<String> = (0..N_TICKERS).map(|i| format!(r#"{{"e":"24hrTicker","E":1672515782136,"s":"SYM{}USDT","p":"0.0015","P":"250.00","w":"0.0018","x":"0.0009","c":"0.0025","Q":"10","b":"0.0024","B":"10","a":"0.0026","A":"100","o":"0.0010","h":"0.0025","l":"0.0010","v":"10000","q":"18","O":0,"C":86400000,"F":0,"L":18150,"n":18151}}"#, i)).collect();
+    format!("[{}]", tickers.join(","))
+}
+
+fn bench_parse_updates(c: &mut Criterion) {
+    let msg = message();
+    let mut group = c.benchmark_group("parse_updates");
+    group.throughput(Throughput::Elements(N_TICKERS as u64));
+    group.bench_function("owned", |b| b.iter(|| {
+        let mut out = Vec::new();
+        parse_owned(&msg, &mut out).unwrap();
+        out
+    }));
+    group.bench_function("borrowed", |b| b.iter(|| parse_updates(&msg).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_updates);
+criterion_main!(benches);
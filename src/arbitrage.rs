@@ -0,0 +1,103 @@
+//! Triangular arbitrage: cycles of three trades that start and end in the same currency, e.g.
+//! USDT → BTC → ETH → USDT via `BTCUSDT`, `ETHBTC` and `ETHUSDT`.
+//!
+//! The return of a cycle is what one unit of the start currency turns into after the three trades
+//! at the best bid or ask, minus fees. With consistent prices it is slightly below 1, so cycles far
+//! from 1 point to an opportunity or to stale prices.
+use crate::utils::*;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+/// One trade of a cycle: `symbol` is sold (base → quote) or bought (quote → base)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leg {
+    pub symbol: Symbol,
+    pub sell: bool,
+}
+
+/// Three trades through the currencies `assets`, starting and ending in `assets[0]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cycle {
+    pub assets: [Symbol; 3],
+    pub legs: [Leg; 3],
+}
+
+impl Cycle {
+    /// Amount of the start currency after the three trades per unit put in, `None` if a price is
+    /// missing. `quote` gives the best bid and ask of a symbol, `fee` is the fee per trade as a fraction.
+    pub fn rate<F>(&self, quote: &F, fee: f64) -> Option<f64>
+        where F: Fn(&Symbol) -> Option<(f64, f64)> {
+        self.legs.iter().try_fold(1.0, |amount, leg| {
+            let (bid, ask) = quote(&leg.symbol)?;
+            let px = if leg.sell { bid } else { 1.0 / ask };
+            if !px.is_finite() || px <= 0.0 { return None; }
+            Some(amount * px * (1.0 - fee))
+        })
+    }
+    /// The currencies of the cycle, e.g. `USDT → BTC → ETH → USDT`
+    pub fn path(&self) -> String {
+        format!("{} → {} → {} → {}", self.assets[0], self.assets[1], self.assets[2], self.assets[0])
+    }
+}
+
+/// All cycles through three currencies that trade with each other, in both directions.
+/// A cycle starts at the currency that is the quote of both its markets if there is one.
+pub fn cycles(infos: &[Info]) -> Vec<Cycle> {
+    let mut markets: HashMap<(&str, &str), &Symbol> = HashMap::new();
+    let mut neighbours: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+    for info in infos {
+        markets.entry((&info.base, &info.quote)).or_insert(&info.symbol);
+        neighbours.entry(&info.base).or_default().insert(&info.quote);
+        neighbours.entry(&info.quote).or_default().insert(&info.base);
+    }
+    // trade from `from` to `to`
+    let leg = |from: &str, to: &str| -> Leg {
+        match markets.get(&(from, to)) {
+            Some(symbol) => Leg { symbol: (*symbol).clone(), sell: true },
+            None => Leg { symbol: markets[&(to, from)].clone(), sell: false },
+        }
+    };
+    let cycle = |a: &str, b: &str, c: &str| -> Cycle {
+        Cycle { assets: [Symbol::from(a), Symbol::from(b), Symbol::from(c)], legs: [leg(a, b), leg(b, c), leg(c, a)] }
+    };
+    let mut cycles = Vec::new();
+    for (a, na) in neighbours.iter() {
+        // each triangle a < b < c once
+        for b in na.range::<&str, _>((Bound::Excluded(*a), Bound::Unbounded)) {
+            for c in neighbours[b].range::<&str, _>((Bound::Excluded(*b), Bound::Unbounded)) {
+                if !na.contains(c) { continue; }
+                let is_quote = |x: &str, y: &str, z: &str| markets.contains_key(&(y, x)) && markets.contains_key(&(z, x));
+                let (s, p, q) = if is_quote(b, a, c) { (*b, *a, *c) }
+                                else if is_quote(c, a, b) { (*c, *a, *b) }
+                                else { (*a, *b, *c) };
+                cycles.push(cycle(s, p, q));
+                cycles.push(cycle(s, q, p));
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangles() {
+        let infos = vec![Info::trading("BTC", "USDT", 1), Info::trading("ETH", "BTC", 1), Info::trading("ETH", "USDT", 1), Info::trading("XRP", "USDT", 1)];
+        let cycles = cycles(&infos);
+        assert_eq!(cycles.len(), 2);
+        assert!(cycles.iter().all(|c| c.assets[0] == "USDT"));
+        let quotes: HashMap<&str, (f64, f64)> = [("BTCUSDT", (100.0, 100.0)), ("ETHBTC", (0.05, 0.05)), ("ETHUSDT", (5.1, 5.1))]
+            .iter().cloned().collect();
+        let quote = |s: &Symbol| quotes.get(&**s).cloned();
+        let forward = cycles.iter().find(|c| c.path() == "USDT → BTC → ETH → USDT").unwrap();
+        assert_eq!(forward.legs[0], Leg { symbol: Symbol::from("BTCUSDT"), sell: false });
+        assert!((forward.rate(&quote, 0.0).unwrap() - 1.02).abs() < 1e-9);
+        assert!((forward.rate(&quote, 0.001).unwrap() - 1.02 * 0.999f64.powi(3)).abs() < 1e-9);
+        let backward = cycles.iter().find(|c| c.path() == "USDT → ETH → BTC → USDT").unwrap();
+        assert!((backward.rate(&quote, 0.0).unwrap() - 5.0 / 5.1).abs() < 1e-9);
+        let missing = |s: &Symbol| if *s == "ETHBTC" { None } else { quote(s) };
+        assert_eq!(forward.rate(&missing, 0.0), None);
+    }
+}
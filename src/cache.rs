@@ -0,0 +1,165 @@
+//! On-disk cache of klines, one file per symbol and interval in `coinlive/klines` in the user's cache
+//! directory (e.g. `~/.cache/coinlive/klines` on Linux).
+//!
+//! Only the bars after the last cached one are downloaded, and cached charts can be shown offline.
+//! When the cache grows beyond `max_size` MB the least recently written files are removed.
+use crate::utils::*;
+use crate::config::KlineCacheConfig;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of bars in a chart
+pub const KLINES_LIMIT: usize = 1000;
+
+/// Numbers the temporary files of `KlineCache::store`
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The kline cache, `dir` is `None` if it is disabled
+#[derive(Debug, Clone)]
+pub struct KlineCache {
+    dir: Option<PathBuf>,
+    max_bytes: u64,
+}
+
+impl KlineCache {
+    /// The cache in the user's cache directory, disabled if `config.enabled` is false
+    pub fn new(config: &KlineCacheConfig) -> Self {
+        let dir = if config.enabled { dirs::cache_dir().map(|dir| dir.join("coinlive").join("klines")) } else { None };
+        KlineCache::in_dir(dir, config.max_size)
+    }
+    /// The cache in `dir` with at most `max_size` MB
+    pub fn in_dir(dir: Option<PathBuf>, max_size: f64) -> Self {
+        KlineCache { dir, max_bytes: (max_size.max(0.0) * 1e6) as u64 }
+    }
+    /// A cache that stores nothing
+    pub fn disabled() -> Self {
+        KlineCache { dir: None, max_bytes: 0 }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+    /// File of `symbol` and `interval`
+    fn path(&self, symbol: &Symbol, interval: &Interval) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}_{}.json", symbol, interval.str())))
+    }
+    /// Cached bars of `symbol` and `interval`, oldest first. Empty if there are none or the file is unreadable.
+    pub fn load(&self, symbol: &Symbol, interval: &Interval) -> Vec<Bar> {
+        self.path(symbol, interval)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+    /// Save `bars` of `symbol` and `interval`, then remove old files if the cache is too large
+    pub fn store(&self, symbol: &Symbol, interval: &Interval, bars: &[Bar]) -> Result<(), String> {
+        let (dir, path) = match (&self.dir, self.path(symbol, interval)) {
+            (Some(dir), Some(path)) => (dir, path),
+            _ => return Ok(()),
+        };
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        let s = serde_json::to_string(bars).map_err(|e| format!("Failed to serialize klines: {}", e))?;
+        // write to a temporary file first, so that a crash never leaves a truncated file. Its name is
+        // unique, loads of the same klines can overlap when a cancelled one is still running.
+        let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp = path.with_extension(format!("{}-{}.tmp", std::process::id(), n));
+        std::fs::write(&tmp, s).map_err(|e| format!("Failed to write {:?}: {}", tmp, e))?;
+        std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        self.enforce_limit()
+    }
+    /// Remove the least recently written files until the cache is at most `max_bytes`
+    fn enforce_limit(&self) -> Result<(), String> {
+        let dir = match &self.dir { Some(dir) => dir, None => return Ok(()) };
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read {:?}: {}", dir, e))?;
+        let mut files: Vec<(std::time::SystemTime, u64, PathBuf)> = entries.flatten().filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            if !meta.is_file() { return None; }
+            Some((meta.modified().ok()?, meta.len(), entry.path()))
+        }).collect();
+        files.sort();
+        let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+        for (_, len, path) in files.iter() {
+            if size <= self.max_bytes { break; }
+            std::fs::remove_file(path).map_err(|e| format!("Failed to remove {:?}: {}", path, e))?;
+            size -= len;
+        }
+        Ok(())
+    }
+}
+
+/// Open time from which bars are missing in `cached` at `now` (millis), `None` if all `KLINES_LIMIT`
+/// bars must be downloaded. The last cached bar is downloaded again, it was probably not complete.
+pub fn missing_since(cached: &[Bar], interval: &Interval, now: u64) -> Option<u64> {
+    let last = cached.last()?;
+    let missing = now.saturating_sub(last.t) / (interval.seconds() as u64 * 1000);
+    if missing as usize >= KLINES_LIMIT { None } else { Some(last.t) }
+}
+
+/// `cached` followed by `fresh`, which replaces cached bars with the same or a later open time,
+/// keeping the last `KLINES_LIMIT` bars
+pub fn merge(mut cached: Vec<Bar>, fresh: Vec<Bar>) -> Vec<Bar> {
+    if let Some(first) = fresh.first() {
+        let t = first.t;
+        cached.retain(|bar| bar.t < t);
+    }
+    cached.extend(fresh);
+    let excess = cached.len().saturating_sub(KLINES_LIMIT);
+    cached.drain(..excess);
+    cached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars(ts: std::ops::Range<u64>, c: f32) -> Vec<Bar> {
+        ts.map(|t| Bar { t: t * 60000, o: c, h: c, l: c, c, v: 1.0 }).collect()
+    }
+
+    #[test]
+    fn tail() {
+        let cached = bars(0..10, 1.0);
+        assert_eq!(missing_since(&cached, &Interval::I1m, 12 * 60000), Some(9 * 60000));
+        assert_eq!(missing_since(&cached, &Interval::I1m, 5000 * 60000), None);
+        assert_eq!(missing_since(&[], &Interval::I1m, 0), None);
+        let merged = merge(cached, bars(9..12, 2.0));
+        assert_eq!(merged.len(), 12);
+        assert_eq!((merged[8].c, merged[9].c), (1.0, 2.0));
+        assert_eq!(merge(bars(0..KLINES_LIMIT as u64, 1.0), bars(2000..2010, 2.0)).len(), KLINES_LIMIT);
+    }
+
+    #[test]
+    fn store_and_limit() -> Result<(), String> {
+        let dir = std::env::temp_dir().join(format!("coinlive-test-{}", std::process::id()));
+        let cache = KlineCache::in_dir(Some(dir.clone()), 1.0);
+        let (btc, eth) = (Symbol::from("BTCUSDT"), Symbol::from("ETHUSDT"));
+        cache.store(&btc, &Interval::I1m, &bars(0..10, 1.0))?;
+        assert_eq!(cache.load(&btc, &Interval::I1m), bars(0..10, 1.0));
+        assert!(cache.load(&btc, &Interval::I5m).is_empty());
+        // too small for both files, the older one goes
+        let size = std::fs::metadata(dir.join("BTCUSDT_1m.json")).map_err(|e| e.to_string())?.len();
+        let small = KlineCache::in_dir(Some(dir.clone()), (size + 10) as f64 / 1e6);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        small.store(&eth, &Interval::I1m, &bars(0..10, 1.0))?;
+        assert!(small.load(&btc, &Interval::I1m).is_empty());
+        assert_eq!(small.load(&eth, &Interval::I1m).len(), 10);
+        std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_stores() -> Result<(), String> {
+        let dir = std::env::temp_dir().join(format!("coinlive-test-concurrent-{}", std::process::id()));
+        let cache = KlineCache::in_dir(Some(dir.clone()), 1.0);
+        let btc = Symbol::from("BTCUSDT");
+        let threads: Vec<_> = (0..8).map(|i| {
+            let (cache, btc) = (cache.clone(), btc.clone());
+            std::thread::spawn(move || cache.store(&btc, &Interval::I1m, &bars(0..100, i as f32)))
+        }).collect();
+        for thread in threads { thread.join().map_err(|_| "store panicked")??; }
+        // one of the writes wins as a whole
+        let loaded = cache.load(&btc, &Interval::I1m);
+        assert_eq!(loaded.len(), 100);
+        assert!(loaded.iter().all(|bar| bar.c == loaded[0].c));
+        std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! User configuration, read from `coinlive/config.toml` in the user's config directory
+//! (e.g. `~/.config/coinlive/config.toml` on Linux).
+//!
+//! Every field has a default, so the file is optional and only needs to contain what differs.
+use crate::filter::{Rule, default_rules};
+use crate::keys::KeysConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Name of the config file inside the config directory
+const CONFIG_FILE: &str = "config.toml";
+
+/// The complete user configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Colour theme, see `theme`
+    pub theme: String,                      // plain values must come before tables for serialization
+    /// Currency the price list is shown in, the quote of each symbol if not set
+    pub currency: Option<String>,
+    /// Quote currencies shown as columns of the price table, the quotes with the most volume if empty
+    pub table_quotes: Vec<String>,
+    /// Show sparklines of the prices since start-up in the price list
+    pub sparklines: bool,
+    pub large_trades: LargeTradesConfig,
+    pub arbitrage: ArbitrageConfig,
+    pub movers: MoversConfig,
+    pub history: HistoryConfig,
+    pub kline_cache: KlineCacheConfig,
+    /// Search aliases, e.g. `bitcoin = "BTC"`
+    pub aliases: HashMap<String, String>,
+    /// Symbol filter rules, see `filter`
+    pub filters: Vec<Rule>,
+    /// Key bindings, see `keys`
+    pub keys: KeysConfig,
+    /// Custom colour themes: name to colours
+    pub themes: HashMap<String, HashMap<String, String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { theme: String::from("dark"), currency: None, table_quotes: Vec::new(), sparklines: true,
+                 large_trades: LargeTradesConfig::default(),
+                 arbitrage: ArbitrageConfig::default(), movers: MoversConfig::default(),
+                 history: HistoryConfig::default(), kline_cache: KlineCacheConfig::default(), aliases: default_aliases(),
+                 filters: default_rules(), keys: KeysConfig::default(), themes: HashMap::new() }
+    }
+}
+
+/// Names of some popular coins
+fn default_aliases() -> HashMap<String, String> {
+    [ ("bitcoin", "BTC"), ("ethereum", "ETH"), ("ether", "ETH"), ("binance", "BNB"), ("ripple", "XRP")
+    , ("cardano", "ADA"), ("solana", "SOL"), ("dogecoin", "DOGE"), ("polkadot", "DOT"), ("litecoin", "LTC")
+    , ("tron", "TRX"), ("chainlink", "LINK"), ("avalanche", "AVAX"), ("polygon", "MATIC"), ("tether", "USDT")
+    , ("euro", "EUR"), ("pound", "GBP")
+    ].iter().map(|(k, v)| (String::from(*k), String::from(*v))).collect()
+}
+
+/// Settings of the large trade (whale) detector
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LargeTradesConfig {
+    /// Global notional threshold in USDT, 0 disables the detector
+    pub threshold: f64,
+    /// Number of symbols (by volume) whose trades are monitored, in addition to those in `thresholds`
+    pub symbols: usize,
+    /// Show large trades in the message bar
+    pub notify: bool,
+    /// Per symbol notional thresholds in USDT, e.g. `BTCUSDT = 1000000`
+    pub thresholds: HashMap<String, f64>,   // tables must come last for serialization
+}
+
+impl Default for LargeTradesConfig {
+    fn default() -> Self {
+        LargeTradesConfig { threshold: 100000.0, symbols: 20, notify: true, thresholds: HashMap::new() }
+    }
+}
+
+/// Settings of the triangular arbitrage page
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ArbitrageConfig {
+    /// Fee per trade in %
+    pub fee: f64,
+}
+
+impl Default for ArbitrageConfig {
+    fn default() -> Self {
+        ArbitrageConfig { fee: 0.1 }
+    }
+}
+
+/// Settings of the top movers page
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MoversConfig {
+    /// Number of symbols per panel
+    pub count: usize,
+    /// Minimum 24h volume in USDT, so that illiquid symbols don't dominate
+    pub min_volume: f64,
+}
+
+impl Default for MoversConfig {
+    fn default() -> Self {
+        MoversConfig { count: 20, min_volume: 1000000.0 }
+    }
+}
+
+/// Settings of the in-memory price history, see `history`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Maximum number of ticks per symbol
+    pub depth: usize,
+    /// Maximum memory of all ticks in MB
+    pub max_memory: f64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig { depth: 1000, max_memory: 32.0 }
+    }
+}
+
+/// Settings of the on-disk kline cache, see `cache`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KlineCacheConfig {
+    /// Use the cache, can also be turned off with `--no-cache`
+    pub enabled: bool,
+    /// Maximum size of the cache in MB
+    pub max_size: f64,
+}
+
+impl Default for KlineCacheConfig {
+    fn default() -> Self {
+        KlineCacheConfig { enabled: true, max_size: 50.0 }
+    }
+}
+
+impl Config {
+    /// Location of the config file, `None` if there is no config directory
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("coinlive").join(CONFIG_FILE))
+    }
+    /// Load the config file. A missing file gives the default config.
+    pub fn load() -> Result<Config, String> {
+        let path = match Config::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+        let s = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        toml::from_str(&s).map_err(|e| format!("Bad config {:?}: {}", path, e))
+    }
+    /// Write `fields` of the config to the config file, keeping everything else in the file, so that the
+    /// defaults are not written. Creates the config directory if necessary.
+    pub fn save(&self, fields: &[&str]) -> Result<(), String> {
+        let path = Config::path().ok_or_else(|| String::from("No config directory"))?;
+        let existing = match std::fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Failed to read {:?}: {}", path, e)),
+        };
+        let s = self.merge_into(&existing, fields).map_err(|e| format!("Not saving config {:?}: {}", path, e))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        std::fs::write(&path, s).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+    /// The config file `existing` with `fields` replaced by those of `self`. Fields that are `None` are removed.
+    fn merge_into(&self, existing: &str, fields: &[&str]) -> Result<String, String> {
+        let mut table: toml::value::Table = toml::from_str(existing).map_err(|e| format!("bad config: {}", e))?;
+        let config = toml::Value::try_from(self).map_err(|e| format!("failed to serialize config: {}", e))?;
+        for field in fields {
+            match config.get(field) {
+                Some(value) => { table.insert(String::from(*field), value.clone()); },
+                None => { table.remove(*field); },
+            }
+        }
+        // as a `Value`, so that plain values are written before the tables
+        toml::to_string(&toml::Value::Table(table)).map_err(|e| format!("failed to serialize config: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn partial_config() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = toml::from_str("[large_trades]\nthreshold = 5e5\n[large_trades.thresholds]\nBTCUSDT = 2e6\n")?;
+        assert_eq!(config.large_trades.threshold, 500000.0);
+        assert_eq!(config.large_trades.thresholds.get("BTCUSDT"), Some(&2000000.0));
+        assert_eq!(config.large_trades.symbols, LargeTradesConfig::default().symbols);
+        assert_eq!(config.filters, default_rules());
+        let s = toml::to_string(&config)?;
+        let config2: Config = toml::from_str(&s)?;
+        assert_eq!(config2.filters, config.filters);
+        Ok(())
+    }
+
+    #[test]
+    fn save_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let existing = "theme = \"light\"\ncurrency = \"BTC\"\n[keys]\npreset = \"vi\"\n";
+        let mut config: Config = toml::from_str(existing)?;
+        config.currency = None;
+        config.filters.clear();
+        let s = config.merge_into(existing, &["currency", "filters"])?;
+        let saved: Config = toml::from_str(&s)?;
+        assert_eq!((saved.theme.as_str(), saved.keys.preset.as_str()), ("light", "vi"));
+        assert_eq!(saved.currency, None);
+        assert!(saved.filters.is_empty());
+        assert!(!s.contains("aliases") && !s.contains("bitcoin"));
+        assert!(config.merge_into("theme = ", &["filters"]).is_err());
+        Ok(())
+    }
+}
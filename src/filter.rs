@@ -0,0 +1,170 @@
+//! Rule based filtering of symbols.
+//!
+//! A rule compares one field of an [`Info`] with a value, for example `quote != BUSD`,
+//! `symbol != *UPUSDT` or `volume >= 1000000`. Symbol, base, quote and status are matched
+//! case-insensitively against glob patterns with `*` and `?`, volume is the 24h volume in USDT.
+//!
+//! A symbol passes the filter if it
+//! - matches at least one of the enabled `=` rules of each field that has any,
+//! - matches none of the enabled `!=` rules,
+//! - satisfies all enabled `>=` rules.
+use crate::utils::*;
+use serde::{Deserialize, Serialize};
+
+/// The `Info` field a `Rule` looks at
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field { Symbol, Base, Quote, Status, Volume }
+
+/// Comparison of a `Rule`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op { Is, IsNot, AtLeast }
+
+/// A single filter rule
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Rule {
+    pub field: Field,
+    pub op: Op,
+    pub value: String,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool { true }
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field = match self.field {
+            Field::Symbol => "symbol",
+            Field::Base   => "base",
+            Field::Quote  => "quote",
+            Field::Status => "status",
+            Field::Volume => "volume",
+        };
+        let op = match self.op {
+            Op::Is      => "=",
+            Op::IsNot   => "!=",
+            Op::AtLeast => ">=",
+        };
+        write!(f, "{} {} {}", field, op, self.value)
+    }
+}
+
+impl std::str::FromStr for Rule {
+    type Err = String;
+    /// Parse `<field> <op> <value>`, e.g. `quote != BUSD` or `volume>=1e6`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ix, op, len) = if let Some(ix) = s.find("!=") { (ix, Op::IsNot, 2) }
+                            else if let Some(ix) = s.find(">=") { (ix, Op::AtLeast, 2) }
+                            else if let Some(ix) = s.find('=') { (ix, Op::Is, 1) }
+                            else { return Err(format!("Missing =, != or >= in {:?}", s)); };
+        let field = match s[..ix].trim().to_lowercase().as_str() {
+            "symbol" => Field::Symbol,
+            "base"   => Field::Base,
+            "quote"  => Field::Quote,
+            "status" => Field::Status,
+            "volume" => Field::Volume,
+            other => return Err(format!("Unknown field {:?}, use symbol, base, quote, status or volume", other)),
+        };
+        let value = s[ix+len..].trim().to_uppercase();
+        if value.is_empty() { return Err(String::from("Missing value")); }
+        match (field, op) {
+            (Field::Volume, Op::AtLeast) => {
+                value.parse::<f64>().map_err(|e| format!("Bad volume {:?}: {}", value, e))?;
+            },
+            (Field::Volume, _) => return Err(String::from("Use volume >= <USDT>")),
+            (_, Op::AtLeast) => return Err(String::from(">= is only allowed for volume")),
+            _ => {}
+        }
+        Ok(Rule { field, op, value, enabled: true })
+    }
+}
+
+impl Rule {
+    /// Does `info` match the pattern (or exceed the volume) of this rule? An unknown (NaN) volume, as in the
+    /// partial list shown before the tickers arrive, passes the volume rules.
+    fn matches(&self, info: &Info) -> bool {
+        match self.field {
+            Field::Symbol => glob_match(&self.value, &info.symbol),
+            Field::Base   => glob_match(&self.value, &info.base),
+            Field::Quote  => glob_match(&self.value, &info.quote),
+            Field::Status => glob_match(&self.value, &info.status),
+            Field::Volume => {
+                let volume = dec_to_f64(info.volume);
+                self.value.parse::<f64>().map(|v| volume.is_nan() || volume >= v).unwrap_or(true)
+            },
+        }
+    }
+}
+
+/// Default rules, these used to be hard-coded
+pub fn default_rules() -> Vec<Rule> {
+    ["status = TRADING", "quote != TUSD", "quote != BUSD", "quote != USDC"]
+        .iter().map(|s| s.parse().expect("INTERNAL ERROR")).collect()
+}
+
+/// Does `info` pass all enabled `rules`?
+pub fn passes(rules: &[Rule], info: &Info) -> bool {
+    let enabled = || rules.iter().filter(|r| r.enabled);
+    for field in [Field::Symbol, Field::Base, Field::Quote, Field::Status].iter() {
+        let mut includes = enabled().filter(|r| r.field == *field && r.op == Op::Is).peekable();
+        if includes.peek().is_some() && !includes.any(|r| r.matches(info)) { return false; }
+    }
+    enabled().all(|r| match r.op {
+        Op::Is      => true,    // see above
+        Op::IsNot   => !r.matches(info),
+        Op::AtLeast => r.matches(info),
+    })
+}
+
+/// Apply `rules` to `infos`, keeping the order
+pub fn apply(rules: &[Rule], infos: &[Info]) -> Vec<Info> {
+    infos.iter().filter(|info| passes(rules, info)).cloned().collect()
+}
+
+/// Case-insensitive glob matching with `*` (any number of characters) and `?` (one character)
+pub fn glob_match(pattern: &str, s: &str) -> bool {
+    fn m(p: &[u8], s: &[u8]) -> bool {
+        match (p.first(), s.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => m(&p[1..], s) || (!s.is_empty() && m(p, &s[1..])),
+            (Some(b'?'), Some(_)) => m(&p[1..], &s[1..]),
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b) && m(&p[1..], &s[1..]),
+            _ => false,
+        }
+    }
+    m(pattern.as_bytes(), s.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dec::Decimal64;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*UPUSDT", "BTCUPUSDT"));
+        assert!(glob_match("btc*", "BTCUSDT"));
+        assert!(glob_match("?TC", "BTC"));
+        assert!(!glob_match("*UP*", "BTCUSDT"));
+    }
+
+    #[test]
+    fn rules() -> Result<(), String> {
+        let rules: Vec<Rule> = vec!["quote = USDT".parse()?, "quote=BTC".parse()?,
+                                    "symbol != *UPUSDT".parse()?, "volume >= 1000".parse()?];
+        assert!(passes(&rules, &Info::trading("BTC", "USDT", 5000)));
+        assert!(passes(&rules, &Info::trading("ETH", "BTC", 5000)));
+        assert!(!passes(&rules, &Info::trading("ETH", "EUR", 5000)));
+        assert!(!passes(&rules, &Info::trading("BTCUP", "USDT", 5000)));
+        assert!(!passes(&rules, &Info::trading("DOGE", "USDT", 500)));
+        assert!(passes(&rules, &Info { volume: Decimal64::NAN, ..Info::trading("DOGE", "USDT", 0) }));
+        assert!(passes(&default_rules(), &Info::trading("DOGE", "USDT", 500)));
+        assert!(!passes(&default_rules(), &Info::trading("DOGE", "BUSD", 500)));
+        assert_eq!(format!("{}", rules[2]), "symbol != *UPUSDT");
+        assert!("volume = 5".parse::<Rule>().is_err());
+        assert!("name = 5".parse::<Rule>().is_err());
+        Ok(())
+    }
+}
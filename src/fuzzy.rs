@@ -0,0 +1,86 @@
+//! Fuzzy matching of symbols for the search page.
+//!
+//! The query is matched case-insensitively as a subsequence of the symbol, base and quote of each
+//! [`Info`]. Exact, prefix and consecutive matches score higher than scattered ones. User-defined
+//! aliases (e.g. `bitcoin` → `BTC`) match as if the target base had been typed.
+use crate::utils::*;
+use std::collections::HashMap;
+
+/// Score of `query` as a case-insensitive subsequence of `s`, `None` if it is not one.
+pub fn score(query: &str, s: &str) -> Option<i32> {
+    let q: Vec<char> = query.chars().flat_map(|c| c.to_lowercase()).collect();
+    let s: Vec<char> = s.chars().flat_map(|c| c.to_lowercase()).collect();
+    if q.is_empty() { return Some(0); }
+    if q == s { return Some(1000); }
+    let mut total: i32 = 0;
+    let mut i_s: usize = 0;
+    let mut last: Option<usize> = None;     // index in s of the previous match
+    for c in q.iter() {
+        let ix = i_s + s[i_s..].iter().position(|x| x == c)?;
+        total += 10;
+        if ix == 0 { total += 20; }                                 // match at the start
+        match last {
+            Some(l) if ix == l+1 => { total += 15; },               // consecutive
+            Some(l) => { total -= (ix - l - 1) as i32; },           // gap
+            None => { total -= ix as i32; },                        // leading gap
+        }
+        last = Some(ix);
+        i_s = ix+1;
+    }
+    total -= (s.len() - q.len()) as i32;                            // prefer short candidates
+    Some(total)
+}
+
+/// Best score of `query` for `info`, looking at symbol, base, quote and aliases
+pub fn score_info(query: &str, info: &Info, aliases: &HashMap<String, String>) -> Option<i32> {
+    let mut best: Option<i32> = None;
+    let mut consider = |score: Option<i32>| {
+        if let Some(score) = score {
+            best = Some(best.map(|b| b.max(score)).unwrap_or(score));
+        }
+    };
+    consider(score(query, &info.symbol));
+    consider(score(query, &info.base).map(|s| s + 5));              // base is what people usually mean
+    consider(score(query, &info.quote).map(|s| s - 50));            // quote matches lots of symbols
+    for (alias, base) in aliases.iter() {
+        if base.eq_ignore_ascii_case(&info.base) {
+            consider(score(query, alias));
+        }
+    }
+    best
+}
+
+/// All `infos` matching `query`, best match first. Ties keep the order of `infos`, i.e. by volume.
+pub fn rank<'a>(query: &str, infos: &'a [Info], aliases: &HashMap<String, String>) -> Vec<&'a Info> {
+    if query.is_empty() { return infos.iter().collect(); }
+    let mut scored: Vec<(i32, &Info)> = infos.iter()
+        .filter_map(|info| score_info(query, info, aliases).map(|s| (s, info)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, info)| info).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores() {
+        assert_eq!(score("xyz", "BTCUSDT"), None);
+        assert!(score("btc", "BTC") > score("btc", "BTCUSDT"));
+        assert!(score("btc", "BTCUSDT") > score("btc", "WBTCUSDT"));
+        assert!(score("eth", "ETHBTC") > score("eth", "ETCHUSDT"));
+    }
+
+    #[test]
+    fn ranking() {
+        let infos = vec![Info::trading("BTC", "USDT", 3), Info::trading("ETH", "BTC", 2),
+                         Info::trading("SOL", "USDT", 1), Info::trading("BTC", "EUR", 0)];
+        let mut aliases = HashMap::new();
+        aliases.insert(String::from("solana"), String::from("SOL"));
+        let symbols = |q: &str| rank(q, &infos, &aliases).iter().map(|i| i.symbol.to_string()).collect::<Vec<_>>();
+        assert_eq!(symbols("btc")[..2], ["BTCUSDT", "BTCEUR"]);
+        assert_eq!(symbols("solana"), ["SOLUSDT"]);
+        assert_eq!(symbols("").len(), infos.len());
+    }
+}
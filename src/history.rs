@@ -0,0 +1,107 @@
+//! In-memory history of the live prices: a ring buffer of `(timestamp, price)` ticks per symbol.
+//!
+//! Each symbol keeps its last `depth` ticks, and all symbols together use at most `max_memory` MB,
+//! so with many active symbols each keeps fewer. Views query it for the history since start-up
+//! instead of calling the REST API.
+use crate::utils::*;
+use crate::config::HistoryConfig;
+use std::collections::{HashMap, VecDeque, vec_deque};
+
+/// A price at a time (millis)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    pub ts: u64,
+    pub px: f64,
+}
+
+/// The ticks of all symbols, oldest first
+pub struct TickHistory {
+    depth: usize,       // maximum number of ticks per symbol
+    max_ticks: usize,   // maximum number of ticks of all symbols
+    n_ticks: usize,     // number of ticks of all symbols
+    ticks: HashMap<Symbol, VecDeque<Tick>>,
+}
+
+impl TickHistory {
+    pub fn new(config: &HistoryConfig) -> Self {
+        let max_ticks = (config.max_memory.max(0.0) * 1e6) as usize / std::mem::size_of::<Tick>();
+        TickHistory { depth: config.depth, max_ticks, n_ticks: 0, ticks: HashMap::new() }
+    }
+    /// Number of ticks each of `n_symbols` symbols can keep
+    fn limit(&self, n_symbols: usize) -> usize {
+        self.depth.min(self.max_ticks / n_symbols.max(1))
+    }
+    /// Record price `px` of `symbol` at `ts`. Ticks older than the last one of the symbol are ignored.
+    pub fn push(&mut self, symbol: &Symbol, ts: u64, px: f64) {
+        if !px.is_finite() { return; }
+        let n_symbols = self.ticks.len() + if self.ticks.contains_key(symbol) { 0 } else { 1 };
+        let limit = self.limit(n_symbols);
+        if limit == 0 { return; }
+        let ticks = self.ticks.entry(symbol.clone()).or_default();
+        if ticks.back().map(|last| ts <= last.ts).unwrap_or(false) { return; }
+        ticks.push_back(Tick { ts, px });
+        self.n_ticks += 1;
+        if ticks.len() > limit {
+            ticks.pop_front();
+            self.n_ticks -= 1;
+        }
+        // a new symbol lowers the limit of all others
+        if self.n_ticks > self.max_ticks {
+            for ticks in self.ticks.values_mut() {
+                let excess = ticks.len().saturating_sub(limit);
+                ticks.drain(..excess);
+                self.n_ticks -= excess;
+            }
+        }
+    }
+    /// All ticks of `symbol`
+    pub fn ticks(&self, symbol: &Symbol) -> vec_deque::Iter<'_, Tick> {
+        self.ticks.get(symbol).map(|ticks| ticks.iter()).unwrap_or_default()
+    }
+    /// Ticks of `symbol` at or after `ts`
+    pub fn since(&self, symbol: &Symbol, ts: u64) -> vec_deque::Iter<'_, Tick> {
+        match self.ticks.get(symbol) {
+            Some(ticks) => ticks.range(ticks.partition_point(|tick| tick.ts < ts)..),
+            None => Default::default(),
+        }
+    }
+    /// Number of ticks of all symbols
+    pub fn n_ticks(&self) -> usize {
+        self.n_ticks
+    }
+    /// Memory used by the ticks in bytes, without the overhead of the buffers
+    pub fn memory(&self) -> usize {
+        self.n_ticks * std::mem::size_of::<Tick>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer() {
+        let mut history = TickHistory::new(&HistoryConfig { depth: 3, max_memory: 1.0 });
+        let btc = Symbol::from("BTCUSDT");
+        for ts in 1..=5 { history.push(&btc, ts, ts as f64); }
+        history.push(&btc, 4, 0.0);   // out of order
+        let pxs: Vec<f64> = history.ticks(&btc).map(|t| t.px).collect();
+        assert_eq!(pxs, vec![3.0, 4.0, 5.0]);
+        assert_eq!(history.since(&btc, 4).count(), 2);
+        assert_eq!(history.since(&btc, 6).count(), 0);
+        assert_eq!(history.ticks(&Symbol::from("ETHUSDT")).count(), 0);
+    }
+
+    #[test]
+    fn memory_cap() {
+        // room for 6 ticks of 16 bytes
+        let mut history = TickHistory::new(&HistoryConfig { depth: 100, max_memory: 1e-4 });
+        let (btc, eth) = (Symbol::from("BTCUSDT"), Symbol::from("ETHUSDT"));
+        for ts in 1..=10 { history.push(&btc, ts, 1.0); }
+        assert_eq!(history.ticks(&btc).count(), 6);
+        history.push(&eth, 1, 1.0);
+        assert_eq!(history.ticks(&btc).count(), 3);
+        assert_eq!(history.n_ticks(), 4);
+        assert!(history.memory() <= 100);
+    }
+}
@@ -0,0 +1,458 @@
+//! Key bindings: a registry mapping keys to actions.
+//!
+//! The bindings start from a preset (`default` or `vi`) and can be changed in the `[keys]` section
+//! of the config file, for example
+//! ```toml
+//! [keys]
+//! preset = "vi"
+//! [keys.bindings]
+//! "C-d" = "page_down"
+//! "x" = "none"
+//! ```
+//! Keys are single characters, `C-<char>` (Ctrl), `M-<char>` (Alt), `F1`..`F12` or one of
+//! `Up`, `Down`, `Left`, `Right`, `Home`, `End`, `PgUp`, `PgDn`, `Enter`, `Esc`, `Tab`, `Space`,
+//! `Backspace`, `Delete`, `Insert`. Actions are listed in [`Action::from_str`].
+use crate::ui::{Msg, SortKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use termion::event::Key;
+
+/// What a key does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    PriceList,
+    PriceTable,
+    Graph(Option<u32>),     // graph at the given time scale, or the current one if `None`
+    TogglePercent,
+    ToggleExtended,
+    Search,
+    Trades,
+    LargeTrades,
+    Detail,
+    Sort(SortKey),
+    Filter,
+    Portfolio,
+    Arbitrage,
+    Heatmap,
+    Movers,
+    Currency,
+    Add,
+    Delete,
+    Help,
+    About,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    PageUp,
+    PageDown,
+    Enter,
+    Esc,
+    Unbound,                // unbinds a key
+}
+
+impl Action {
+    /// The message sent to the UI, `None` for `Quit` and `Unbound`
+    pub fn msg(&self) -> Option<Msg> {
+        Some(match self {
+            Action::Quit           => return None,
+            Action::PriceList      => Msg::PriceList,
+            Action::PriceTable     => Msg::PriceTable,
+            Action::Graph(scale)   => Msg::Graph(*scale),
+            Action::TogglePercent  => Msg::TogglePercent,
+            Action::ToggleExtended => Msg::ToggleExtended,
+            Action::Search         => Msg::Search,
+            Action::Trades         => Msg::Trades,
+            Action::LargeTrades    => Msg::LargeTrades,
+            Action::Detail         => Msg::Detail,
+            Action::Sort(key)      => Msg::Sort(*key),
+            Action::Filter         => Msg::Filter,
+            Action::Portfolio      => Msg::Portfolio,
+            Action::Arbitrage      => Msg::Arbitrage,
+            Action::Heatmap        => Msg::Heatmap,
+            Action::Movers         => Msg::Movers,
+            Action::Currency       => Msg::Currency,
+            Action::Add            => Msg::Add,
+            Action::Delete         => Msg::Delete,
+            Action::Help           => Msg::Help,
+            Action::About          => Msg::About,
+            Action::Up             => Msg::ArrowUp,
+            Action::Down           => Msg::ArrowDown,
+            Action::Left           => Msg::ArrowLeft,
+            Action::Right          => Msg::ArrowRight,
+            Action::Home           => Msg::Home,
+            Action::PageUp         => Msg::PageUp,
+            Action::PageDown       => Msg::PageDown,
+            Action::Enter          => Msg::Enter,
+            Action::Esc            => Msg::Esc,
+            Action::Unbound        => return None,
+        })
+    }
+    /// Description for the help page
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit              => "Quit",
+            Action::PriceList         => "Show price list",
+            Action::PriceTable        => "Show price table",
+            Action::Graph(None)       => "Show graph at current time scale",
+            Action::Graph(Some(_))    => "Show graph at time scale 0 to 9 (1m to 1d)",
+            Action::TogglePercent     => "Toggle percent/price display",
+            Action::ToggleExtended    => "Toggle extended/reduced view (Table display)",
+            Action::Search            => "Select symbol: type to search, arrows to move, return to graph",
+            Action::Trades            => "Show trades of selected symbol",
+            Action::LargeTrades       => "Show large trades",
+            Action::Detail            => "Show 24h statistics of selected symbol",
+            Action::Sort(SortKey::Volume) => "Sort price list by volume, again to reverse",
+            Action::Sort(SortKey::Change) => "Sort price list by 24h change, again to reverse",
+            Action::Sort(SortKey::Price)  => "Sort price list by price in USDT, again to reverse",
+            Action::Sort(SortKey::Name)   => "Sort price list by name, again to reverse",
+            Action::Filter            => "Show and edit symbol filter rules",
+            Action::Portfolio         => "Show portfolio valuation",
+            Action::Arbitrage         => "Show triangular arbitrage cycles",
+            Action::Heatmap           => "Show market heatmap",
+            Action::Movers            => "Show top gainers, losers and most active symbols",
+            Action::Currency          => "Cycle currency of price list (quote, USDT, BTC, ETH, EUR)",
+            Action::Add               => "Add filter rule (filter page)",
+            Action::Delete            => "Delete filter rule (filter page)",
+            Action::Help              => "Toggle this help",
+            Action::About             => "Display about page",
+            Action::Up                => "Move cursor up",
+            Action::Down              => "Move cursor down",
+            Action::Left              => "Move cursor left",
+            Action::Right             => "Move cursor right",
+            Action::Home              => "Set cursor to top left symbol (select symbol page)",
+            Action::PageUp            => "Scroll one page up (list and select symbol pages)",
+            Action::PageDown          => "Scroll one page down (list and select symbol pages)",
+            Action::Enter             => "Show graph of selected symbol, toggle filter rule",
+            Action::Esc               => "Go back to previous view",
+            Action::Unbound           => "Nothing",
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Action::Quit           => "quit",
+            Action::PriceList      => "price_list",
+            Action::PriceTable     => "price_table",
+            Action::Graph(None)    => "graph",
+            Action::Graph(Some(n)) => return write!(f, "graph_{}", n),
+            Action::TogglePercent  => "toggle_percent",
+            Action::ToggleExtended => "toggle_extended",
+            Action::Search         => "search",
+            Action::Trades         => "trades",
+            Action::LargeTrades    => "large_trades",
+            Action::Detail         => "detail",
+            Action::Sort(key)      => return write!(f, "sort_{}", key.str()),
+            Action::Filter         => "filter",
+            Action::Portfolio      => "portfolio",
+            Action::Arbitrage      => "arbitrage",
+            Action::Heatmap        => "heatmap",
+            Action::Movers         => "movers",
+            Action::Currency       => "currency",
+            Action::Add            => "add",
+            Action::Delete         => "delete",
+            Action::Help           => "help",
+            Action::About          => "about",
+            Action::Up             => "up",
+            Action::Down           => "down",
+            Action::Left           => "left",
+            Action::Right          => "right",
+            Action::Home           => "home",
+            Action::PageUp         => "page_up",
+            Action::PageDown       => "page_down",
+            Action::Enter          => "enter",
+            Action::Esc            => "esc",
+            Action::Unbound        => "none",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = String;
+    /// Parse an action: `quit`, `price_list`, `price_table`, `graph`, `graph_0`..`graph_9`,
+    /// `toggle_percent`, `toggle_extended`, `search`, `trades`, `large_trades`, `detail`,
+    /// `sort_volume`, `sort_change`, `sort_price`, `sort_name`, `filter`, `portfolio`, `arbitrage`, `heatmap`,
+    /// `movers`, `currency`, `add`, `delete`, `help`, `about`, `up`, `down`, `left`, `right`, `home`, `page_up`,
+    /// `page_down`, `enter`, `esc`, `none`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "quit"            => Action::Quit,
+            "price_list"      => Action::PriceList,
+            "price_table"     => Action::PriceTable,
+            "graph"           => Action::Graph(None),
+            "toggle_percent"  => Action::TogglePercent,
+            "toggle_extended" => Action::ToggleExtended,
+            "search"          => Action::Search,
+            "trades"          => Action::Trades,
+            "large_trades"    => Action::LargeTrades,
+            "detail"          => Action::Detail,
+            "sort_volume"     => Action::Sort(SortKey::Volume),
+            "sort_change"     => Action::Sort(SortKey::Change),
+            "sort_price"      => Action::Sort(SortKey::Price),
+            "sort_name"       => Action::Sort(SortKey::Name),
+            "filter"          => Action::Filter,
+            "portfolio"       => Action::Portfolio,
+            "arbitrage"       => Action::Arbitrage,
+            "heatmap"         => Action::Heatmap,
+            "movers"          => Action::Movers,
+            "currency"        => Action::Currency,
+            "add"             => Action::Add,
+            "delete"          => Action::Delete,
+            "help"            => Action::Help,
+            "about"           => Action::About,
+            "up"              => Action::Up,
+            "down"            => Action::Down,
+            "left"            => Action::Left,
+            "right"           => Action::Right,
+            "home"            => Action::Home,
+            "page_up"         => Action::PageUp,
+            "page_down"       => Action::PageDown,
+            "enter"           => Action::Enter,
+            "esc"             => Action::Esc,
+            "none"            => Action::Unbound,
+            _ => match s.strip_prefix("graph_").and_then(|n| n.parse::<u32>().ok()) {
+                Some(n) if n <= 9 => Action::Graph(Some(n)),
+                _ => return Err(format!("Unknown action {:?}", s)),
+            }
+        })
+    }
+}
+
+/// Name of a key, the inverse of `parse_key`
+pub fn key_name(key: &Key) -> String {
+    match key {
+        Key::Char('\n') => String::from("Enter"),
+        Key::Char('\t') => String::from("Tab"),
+        Key::Char(' ')  => String::from("Space"),
+        Key::Char(c)    => c.to_string(),
+        Key::Ctrl(c)    => format!("C-{}", c),
+        Key::Alt(c)     => format!("M-{}", c),
+        Key::F(n)       => format!("F{}", n),
+        Key::Up         => String::from("Up"),
+        Key::Down       => String::from("Down"),
+        Key::Left       => String::from("Left"),
+        Key::Right      => String::from("Right"),
+        Key::Home       => String::from("Home"),
+        Key::End        => String::from("End"),
+        Key::PageUp     => String::from("PgUp"),
+        Key::PageDown   => String::from("PgDn"),
+        Key::Esc        => String::from("Esc"),
+        Key::Backspace  => String::from("Backspace"),
+        Key::Delete     => String::from("Delete"),
+        Key::Insert     => String::from("Insert"),
+        key             => format!("{:?}", key),
+    }
+}
+
+/// Parse a key name, see the module documentation
+pub fn parse_key(s: &str) -> Result<Key, String> {
+    let mut chars = s.chars();
+    let single = |rest: &str| -> Option<char> {
+        let mut cs = rest.chars();
+        match (cs.next(), cs.next()) { (Some(c), None) => Some(c), _ => None }
+    };
+    if let (Some(c), None) = (chars.next(), chars.next()) { return Ok(Key::Char(c)); }
+    if let Some(c) = s.strip_prefix("C-").and_then(single) { return Ok(Key::Ctrl(c)); }
+    if let Some(c) = s.strip_prefix("M-").and_then(single) { return Ok(Key::Alt(c)); }
+    if let Some(n) = s.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+        if (1..=12).contains(&n) { return Ok(Key::F(n)); }
+    }
+    Ok(match s.to_lowercase().as_str() {
+        "up"                => Key::Up,
+        "down"              => Key::Down,
+        "left"              => Key::Left,
+        "right"             => Key::Right,
+        "home"              => Key::Home,
+        "end"               => Key::End,
+        "pgup" | "pageup"   => Key::PageUp,
+        "pgdn" | "pagedown" => Key::PageDown,
+        "enter" | "return"  => Key::Char('\n'),
+        "esc" | "escape"    => Key::Esc,
+        "tab"               => Key::Char('\t'),
+        "space"             => Key::Char(' '),
+        "backspace"         => Key::Backspace,
+        "delete" | "del"    => Key::Delete,
+        "insert" | "ins"    => Key::Insert,
+        _ => return Err(format!("Unknown key {:?}", s)),
+    })
+}
+
+/// The `[keys]` section of the config file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeysConfig {
+    /// `default` or `vi`
+    pub preset: String,
+    /// Bindings that replace those of the preset, e.g. `"C-d" = "page_down"`
+    pub bindings: HashMap<String, String>,  // tables must come last for serialization
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        KeysConfig { preset: String::from("default"), bindings: HashMap::new() }
+    }
+}
+
+/// The key binding registry, in the order shown on the help page
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        use Action::*;
+        let mut bindings = vec![
+            // help and pages
+            (Key::Char('h'), Help),
+            (Key::Char('?'), Help),
+            (Key::Char('l'), PriceList),
+            (Key::Char('t'), PriceTable),
+            (Key::Char('g'), Graph(None)),
+        ];
+        // graph time scales
+        bindings.extend((0..=9).map(|n| (Key::Char(std::char::from_digit(n, 10).unwrap_or('0')), Graph(Some(n)))));
+        bindings.extend(vec![
+            // pages of the selected symbol
+            (Key::Char('s'), Search),
+            (Key::Char('r'), Trades),
+            (Key::Char('d'), Detail),
+            (Key::Char('w'), LargeTrades),
+            // navigation
+            (Key::Up, Up),
+            (Key::Down, Down),
+            (Key::Left, Left),
+            (Key::Right, Right),
+            (Key::Home, Home),
+            (Key::PageUp, PageUp),
+            (Key::PageDown, PageDown),
+            (Key::Char('\n'), Enter),
+            // price list order
+            (Key::Char('v'), Sort(SortKey::Volume)),
+            (Key::Char('c'), Sort(SortKey::Change)),
+            (Key::Char('p'), Sort(SortKey::Price)),
+            (Key::Char('n'), Sort(SortKey::Name)),
+            // filter, portfolio and market overview pages
+            (Key::Char('f'), Filter),
+            (Key::Char('o'), Portfolio),
+            (Key::Char('b'), Arbitrage),
+            (Key::Char('m'), Heatmap),
+            (Key::Char('u'), Movers),
+            // price list currency, adding and deleting filter rules
+            (Key::Char('$'), Currency),
+            (Key::Char('+'), Add),
+            (Key::Char('-'), Delete),
+            (Key::Delete, Delete),
+            // display
+            (Key::Char('%'), TogglePercent),
+            (Key::Char('x'), ToggleExtended),
+            (Key::Char('a'), About),
+            // leaving
+            (Key::Esc, Esc),
+            (Key::Char('q'), Quit),
+            (Key::Ctrl('c'), Quit),
+        ]);
+        KeyMap { bindings }
+    }
+}
+
+impl KeyMap {
+    /// The bindings of `config`: its preset, changed by its bindings
+    pub fn new(config: &KeysConfig) -> Result<KeyMap, String> {
+        let mut keys = match config.preset.as_str() {
+            "default" => KeyMap::default(),
+            "vi" => KeyMap::vi(),
+            other => return Err(format!("Unknown key preset {:?}, use default or vi", other)),
+        };
+        let mut bindings: Vec<(&String, &String)> = config.bindings.iter().collect();
+        bindings.sort();    // deterministic order on the help page
+        for (key, action) in bindings {
+            keys.bind(parse_key(key)?, action.parse()?);
+        }
+        Ok(keys)
+    }
+    /// Vi-style preset: `hjkl` move, `C-f`/`C-b` page, `?` help, `/` search, `L` price list
+    pub fn vi() -> KeyMap {
+        let mut keys = KeyMap::default();
+        for (key, action) in [
+            (Key::Char('/'), Action::Search), (Key::Char('L'), Action::PriceList),
+            (Key::Char('h'), Action::Left), (Key::Char('j'), Action::Down),
+            (Key::Char('k'), Action::Up), (Key::Char('l'), Action::Right), (Key::Char('H'), Action::Home),
+            (Key::Ctrl('f'), Action::PageDown), (Key::Ctrl('b'), Action::PageUp),
+            (Key::Ctrl('d'), Action::PageDown), (Key::Ctrl('u'), Action::PageUp),
+        ].iter() {
+            keys.bind(*key, *action);
+        }
+        keys
+    }
+    /// Bind `key` to `action`, replacing any previous binding of `key`
+    pub fn bind(&mut self, key: Key, action: Action) {
+        match self.bindings.iter_mut().find(|(k, _)| *k == key) {
+            Some(binding) => { binding.1 = action; },
+            None => { self.bindings.push((key, action)); },
+        }
+    }
+    /// The action bound to `key`
+    pub fn get(&self, key: &Key) -> Option<Action> {
+        self.bindings.iter().find(|(k, _)| k == key).map(|(_, action)| *action).filter(|a| *a != Action::Unbound)
+    }
+    /// Help lines of the actions passing `filter`: key names and description, keys with the same
+    /// description are combined
+    pub fn help<F: Fn(&Action) -> bool>(&self, filter: F) -> Vec<(String, &'static str)> {
+        let mut lines: Vec<(Vec<String>, &'static str)> = Vec::new();
+        for (key, action) in self.bindings.iter().filter(|(_, a)| *a != Action::Unbound && filter(a)) {
+            let description = action.description();
+            match lines.iter_mut().find(|(_, d)| *d == description) {
+                Some((keys, _)) => keys.push(key_name(key)),
+                None => lines.push((vec![key_name(key)], description)),
+            }
+        }
+        lines.into_iter().map(|(keys, description)| {
+            let keys = if keys.len() > 3 { format!("{}..{}", keys[0], keys[keys.len()-1]) } else { keys.join(", ") };
+            (keys, description)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_and_actions() {
+        for s in ["q", "C-c", "M-x", "F5", "Up", "PgDn", "Enter", "Esc", "Space", "?"].iter() {
+            assert_eq!(key_name(&parse_key(s).unwrap()), *s);
+        }
+        assert!(parse_key("foo").is_err());
+        for s in ["graph", "graph_7", "sort_change", "page_up", "none"].iter() {
+            assert_eq!(s.parse::<Action>().unwrap().to_string(), *s);
+        }
+        assert!("graph_10".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn presets_and_bindings() -> Result<(), String> {
+        let keys = KeyMap::default();
+        assert_eq!(keys.get(&Key::Char('h')), Some(Action::Help));
+        assert_eq!(keys.get(&Key::Char('7')), Some(Action::Graph(Some(7))));
+        assert!(keys.help(|_| true).contains(&(String::from("0..9"), Action::Graph(Some(0)).description())));
+        let mut config = KeysConfig { preset: String::from("vi"), bindings: HashMap::new() };
+        config.bindings.insert(String::from("x"), String::from("none"));
+        config.bindings.insert(String::from("C-n"), String::from("down"));
+        let keys = KeyMap::new(&config)?;
+        assert_eq!(keys.get(&Key::Char('h')), Some(Action::Left));
+        assert_eq!(keys.get(&Key::Char('?')), Some(Action::Help));
+        assert_eq!(keys.get(&Key::Char('x')), None);
+        assert_eq!(keys.get(&Key::Ctrl('n')), Some(Action::Down));
+        assert!(keys.help(|_| true).iter().any(|(k, _)| k == "Down, j, C-n"));
+        assert!(keys.help(|a| *a == Action::Help).iter().all(|(k, _)| k == "?"));
+        config.preset = String::from("emacs");
+        assert!(KeyMap::new(&config).is_err());
+        Ok(())
+    }
+}
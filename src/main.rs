@@ -1,16 +1,32 @@
 mod utils;
 mod ui;
+mod streams;
+mod config;
+mod filter;
+mod fuzzy;
+mod keys;
+mod theme;
+mod portfolio;
+mod arbitrage;
+mod history;
+mod cache;
 use crate::{
     utils::*,
-    ui::*
+    ui::*,
+    config::Config,
+    keys::{Action, KeyMap, key_name},
+    theme::Theme,
+    cache::KlineCache
 };
 use std::{
     io,
-    time::Duration
+    time::Duration,
+    sync::Arc,
+    sync::atomic::{AtomicBool, Ordering}
 };
 use termion::{
-    event::Key,
-    input::TermRead,
+    event::{Event, Key},
+    input::{MouseTerminal, TermRead},
     raw::IntoRawMode
 };
 use tui::{Terminal, backend::TermionBackend};
@@ -18,7 +34,7 @@ use tokio_tungstenite::{connect_async};
 use tokio::sync::mpsc::UnboundedSender;
 use futures_util::{future, StreamExt};
 use url::Url;
-use clap::{Command};
+use clap::{Arg, ArgAction, Command};
 use version::version;
 
 /// Duration of `sleep` in `listen_keys` loop
@@ -30,48 +46,36 @@ const URI_WS_TICKER: &str = "wss://stream.binance.com:9443/ws/!ticker@arr";
 /// Listen to terminal input.
 /// 
 /// This is simply an endless loop that reads the terminal input in `LOOP_SPEED` intervals and sends
-/// the appropriate message to `tx`.
-async fn listen_keys(tx: UnboundedSender<Msg>) -> Result<(), String> {
-    let mut stdin = termion::async_stdin().keys();
+/// the message of the action bound to the key in `keys` to `tx`. While `input_mode` is set, keys are 
+/// sent as `Msg::Input` instead. Mouse events are always sent as `Msg::Mouse`, Ctrl-c always quits.
+async fn listen_keys(tx: UnboundedSender<Msg>, input_mode: Arc<AtomicBool>, keys: KeyMap) -> Result<(), String> {
+    let mut stdin = termion::async_stdin().events();
     loop {
-        if let Some(Ok(key)) = stdin.next() {
-            match key {
-                Key::Char('q') => {
-                    tx.send(Msg::Stop).expect("UI failed");
-                    break;
+        if let Some(Ok(event)) = stdin.next() {
+            let key = match event {
+                Event::Key(key) => key,
+                Event::Mouse(mouse) => {
+                    tx.send(Msg::Mouse(mouse)).map_err(|e| format!("UI failed: {:?}", e))?;
+                    continue;
                 },
-                Key::Ctrl('c') => {
+                Event::Unsupported(_) => continue,
+            };
+            if input_mode.load(Ordering::Relaxed) && key != Key::Ctrl('c') {
+                tx.send(Msg::Input(key)).map_err(|e| format!("UI failed: {:?}", e))?;
+                continue;
+            }
+            let action = if key == Key::Ctrl('c') { Some(Action::Quit) } else { keys.get(&key) };
+            match action {
+                Some(Action::Quit) => {
                     tx.send(Msg::Stop).expect("UI failed");
                     break;
                 },
-                Key::Char('l')  => { tx.send(Msg::PriceList).expect("UI failed"); },
-                Key::Char('t')  => { tx.send(Msg::PriceTable).expect("UI failed"); },
-                Key::Char('%')  => { tx.send(Msg::TogglePercent).expect("UI failed"); },
-                Key::Char('x')  => { tx.send(Msg::ToggleExtended).expect("UI failed"); },
-                Key::Char('s')  => { tx.send(Msg::Search).expect("UI failed"); },
-                Key::Char('h')  => { tx.send(Msg::Help).expect("UI failed"); },
-                Key::Char('a')  => { tx.send(Msg::About).expect("UI failed"); },
-                Key::Char('g')  => { tx.send(Msg::Graph(None)).expect("UI failed"); },
-                Key::Char('0')  => { tx.send(Msg::Graph(Some(0))).expect("UI failed"); },
-                Key::Char('1')  => { tx.send(Msg::Graph(Some(1))).expect("UI failed"); },
-                Key::Char('2')  => { tx.send(Msg::Graph(Some(2))).expect("UI failed"); },
-                Key::Char('3')  => { tx.send(Msg::Graph(Some(3))).expect("UI failed"); },
-                Key::Char('4')  => { tx.send(Msg::Graph(Some(4))).expect("UI failed"); },
-                Key::Char('5')  => { tx.send(Msg::Graph(Some(5))).expect("UI failed"); },
-                Key::Char('6')  => { tx.send(Msg::Graph(Some(6))).expect("UI failed"); },
-                Key::Char('7')  => { tx.send(Msg::Graph(Some(7))).expect("UI failed"); },
-                Key::Char('8')  => { tx.send(Msg::Graph(Some(8))).expect("UI failed"); },
-                Key::Char('9')  => { tx.send(Msg::Graph(Some(9))).expect("UI failed"); },
-                Key::Up         => { tx.send(Msg::ArrowUp).expect("UI failed"); },
-                Key::Down       => { tx.send(Msg::ArrowDown).expect("UI failed"); },
-                Key::Left       => { tx.send(Msg::ArrowLeft).expect("UI failed"); },
-                Key::Right      => { tx.send(Msg::ArrowRight).expect("UI failed"); },
-                Key::Home       => { tx.send(Msg::Home).expect("UI failed"); },
-                Key::Char('\n') => { tx.send(Msg::Enter).expect("UI failed"); },
-                Key::Esc        => { tx.send(Msg::Esc).expect("UI failed"); },
-                key => { 
-                    tx.send(Msg::Msg(format!("Unknown command {:?}", key)))
-                      .map_err(|e| format!("UI failed: {:?}", e))?; 
+                Some(action) => {
+                    if let Some(msg) = action.msg() { tx.send(msg).expect("UI failed"); }
+                },
+                None => {
+                    tx.send(Msg::Msg(format!("Unknown command {}", key_name(&key))))
+                      .map_err(|e| format!("UI failed: {:?}", e))?;
                 }
             }
         }
@@ -80,7 +84,8 @@ async fn listen_keys(tx: UnboundedSender<Msg>) -> Result<(), String> {
     Ok(())
 }
 
-/// Websocket stream
+/// Websocket stream. The ticker updates are parsed here and sent to the `UI` in batches, other
+/// messages are sent as they are.
 async fn ws(uri: &str, ui_tx: UnboundedSender<Msg>) -> Result<(), String> {
     let uri: Url = Url::parse(uri).map_err(|e| format!("Bad url: {:?}", e))?;
     let (ws_stream, response) = match connect_async(uri).await {
@@ -104,9 +109,12 @@ async fn ws(uri: &str, ui_tx: UnboundedSender<Msg>) -> Result<(), String> {
             Some(msg) => {
                 match msg {
                     Ok(msg)  => {
-                        let msg = msg.to_string();
-                        ui_tx.send(Msg::WS(now, msg))
-                             .map_err(|e| format!("UI failed: {:?}", e))?;
+                        let updates = if msg.is_text() { msg.to_text().ok().and_then(|s| parse_updates(s).ok()) } else { None };
+                        let msg = match updates {
+                            Some(updates) => Msg::Updates(updates),
+                            None => Msg::WS(now, msg.to_string()),
+                        };
+                        ui_tx.send(msg).map_err(|e| format!("UI failed: {:?}", e))?;
                     }, 
                     Err(e) => {
                         ui_tx.send(Msg::Msg(format!("Error: {:?}", e)))
@@ -125,16 +133,41 @@ async fn ws(uri: &str, ui_tx: UnboundedSender<Msg>) -> Result<(), String> {
     Ok(())
 }
 
-/// Essentially calls `get_infos`, sorts the `Info` vector and sends the `Msg`s.
+/// Gets symbols and markets concurrently, sorts the `Info` vector and sends the `Msg`s.
+///
+/// The symbols are sent as soon as they arrive, ordered by name, and again sorted by volume once the
+/// markets are there too. The size and duration of each request are shown in the message bar.
 async fn get_symbols_async(tx: UnboundedSender<Msg>) -> Result<(), String> {
-    tx.send(Msg::Msg(String::from("Getting symbols..."))).map_err(|e| format!("UI failed: {:?}", e))?;
-    if let Ok(infos) = get_infos().await {
-        let infos = sort_infos(infos);
-        tx.send(Msg::Msg(format!("Got {} symbols", infos.len()))).map_err(|e| format!("UI failed: {:?}", e))?;
-        tx.send(Msg::Infos(infos)).map_err(|e| format!("UI failed: {:?}", e))?;
-    } else {
-        tx.send(Msg::Msg(String::from("Failed to get symbols"))).map_err(|e| format!("UI failed: {:?}", e))?;
-        tx.send(Msg::Stop).map_err(|e| format!("UI failed: {:?}", e))?; 
+    let send = |msg: Msg| tx.send(msg).map_err(|e| format!("UI failed: {:?}", e));
+    send(Msg::Msg(String::from("Getting symbols and markets...")))?;
+    let symbols = async { get_symbols().await.map_err(|e| format!("Get infos failed: {}", e)) };
+    let markets = async { get_markets().await.map_err(|e| format!("Get markets failed: {}", e)) };
+    tokio::pin!(symbols, markets);
+    let (symbols, markets) = tokio::select! {
+        symbols = &mut symbols => {
+            if let Ok((symbols, fetched)) = &symbols {
+                send(Msg::Msg(format!("Got {} symbols ({}), getting markets...", symbols.len(), fetched)))?;
+                send(Msg::PartialInfos(trading_infos(symbols)))?;
+            }
+            (symbols, markets.await)
+        },
+        markets = &mut markets => {
+            if let Ok((markets, fetched)) = &markets {
+                send(Msg::Msg(format!("Got {} markets ({}), getting symbols...", markets.len(), fetched)))?;
+            }
+            (symbols.await, markets)
+        },
+    };
+    match (symbols, markets) {
+        (Ok((symbols, fetched_symbols)), Ok((markets, fetched_markets))) => {
+            let infos = sort_infos(traded_infos(symbols, &markets));
+            send(Msg::Msg(format!("Got {} symbols: exchange info {}, tickers {}", infos.len(), fetched_symbols, fetched_markets)))?;
+            send(Msg::Infos(infos))?;
+        },
+        (Err(e), _) | (_, Err(e)) => {
+            send(Msg::Msg(format!("Failed to get symbols: {}", e)))?;
+            send(Msg::Stop)?;
+        },
     }
     Ok(())
 }
@@ -143,21 +176,39 @@ async fn get_symbols_async(tx: UnboundedSender<Msg>) -> Result<(), String> {
 #[tokio::main]
 async fn main() -> Result<(),Box<dyn std::error::Error>> {
 
-    let _matches = Command::new("coinlive")
+    let matches = Command::new("coinlive")
         .about("Live cryptocurrency prices CLI")
         .version(version!())
         .author("Mayer Analytics. https://github.com/mayeranalytics/coinlive")
+        .arg(Arg::new("no-cache").long("no-cache").action(ArgAction::SetTrue)
+                .help("Always download klines, don't read or write the kline cache"))
         .get_matches();
 
-    // terminal raw mode to allow reading stdin one key at a time
-    let stdout = io::stdout().into_raw_mode().unwrap();
+    // terminal raw mode to allow reading stdin one key at a time, with mouse reporting
+    let stdout = MouseTerminal::from(io::stdout().into_raw_mode().unwrap());
     let backend = TermionBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
-    let ui = UI::new(terminal);
+    let (config, config_error) = match Config::load() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    let (keys, keys_error) = match KeyMap::new(&config.keys) {
+        Ok(keys) => (keys, None),
+        Err(e) => (KeyMap::default(), Some(format!("Bad key bindings: {}", e))),
+    };
+    let (theme, theme_error) = match Theme::new(&config.theme, &config.themes) {
+        _ if theme::no_color() => (Theme::no_color(), None),
+        Ok(theme) => (theme, None),
+        Err(e) => (Theme::default(), Some(e)),
+    };
+    theme::init(theme);
+    let cache = if matches.get_flag("no-cache") { KlineCache::disabled() } else { KlineCache::new(&config.kline_cache) };
+    let ui = UI::new(terminal, config, config_error.clone(), keys.clone(), cache);
+    if let Some(e) = config_error.or(keys_error).or(theme_error) { ui.tx.send(Msg::Msg(e))?; }
 
     tokio::spawn(get_symbols_async(ui.tx.clone()));
 
-    let listen_keys_handle = tokio::spawn(listen_keys(ui.tx.clone()));
+    let listen_keys_handle = tokio::spawn(listen_keys(ui.tx.clone(), ui.input_mode.clone(), keys));
 
     ui.tx.send(Msg::Msg(String::from("Starting stream... ")))?;
     let ws_task = tokio::spawn(ws(URI_WS_TICKER, ui.tx));
@@ -0,0 +1,105 @@
+//! Portfolio holdings, read from `coinlive/portfolio.toml` in the user's config directory, and
+//! their live valuation in USDT.
+//!
+//! ```toml
+//! [[holdings]]
+//! asset = "BTC"
+//! quantity = 0.5
+//! cost = 12000        # optional cost basis of the whole position in USDT
+//! ```
+use crate::utils::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use dec::Decimal64;
+
+/// Name of the portfolio file inside the config directory
+const PORTFOLIO_FILE: &str = "portfolio.toml";
+
+/// A single holding
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Holding {
+    pub asset: String,
+    pub quantity: f64,
+    /// Cost basis of the whole position in USDT
+    pub cost: Option<f64>,
+}
+
+/// The portfolio file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Portfolio {
+    pub holdings: Vec<Holding>,
+}
+
+impl Portfolio {
+    /// Location of the portfolio file, `None` if there is no config directory
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("coinlive").join(PORTFOLIO_FILE))
+    }
+    /// Load the portfolio file. A missing file gives an empty portfolio.
+    pub fn load() -> Result<Portfolio, String> {
+        let path = match Portfolio::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Portfolio::default()),
+        };
+        let s = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        let mut portfolio: Portfolio = toml::from_str(&s).map_err(|e| format!("Bad portfolio {:?}: {}", path, e))?;
+        for holding in portfolio.holdings.iter_mut() { holding.asset = holding.asset.to_uppercase(); }
+        Ok(portfolio)
+    }
+    /// Value all holdings in USDT with the current prices `price` and those of 24h ago `price_24h`.
+    /// Non-USDT pairs are converted with `cross_rate`.
+    pub fn valuate<F, G>(&self, infos: &[Info], price: F, price_24h: G) -> Vec<Position>
+        where F: Fn(&Symbol) -> Option<Decimal64>, G: Fn(&Symbol) -> Option<Decimal64> {
+        self.holdings.iter().map(|holding| {
+            let px = cross_rate(&holding.asset, "USDT", infos, &price).map(dec_to_f64);
+            let px_24h = cross_rate(&holding.asset, "USDT", infos, &price_24h).map(dec_to_f64);
+            Position { holding: holding.clone(), px, px_24h }
+        }).collect()
+    }
+}
+
+/// A holding valued in USDT, `px` and `px_24h` are `None` if there is no conversion to USDT
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub holding: Holding,
+    pub px: Option<f64>,
+    pub px_24h: Option<f64>,
+}
+
+impl Position {
+    /// Current value in USDT
+    pub fn value(&self) -> Option<f64> {
+        self.px.map(|px| px * self.holding.quantity)
+    }
+    /// Change of value over the last 24h in USDT
+    pub fn pnl_24h(&self) -> Option<f64> {
+        Some(self.value()? - self.px_24h? * self.holding.quantity)
+    }
+    /// Unrealised profit or loss against the cost basis in USDT
+    pub fn pnl(&self) -> Option<f64> {
+        Some(self.value()? - self.holding.cost?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn valuation() -> Result<(), Box<dyn std::error::Error>> {
+        let portfolio: Portfolio = toml::from_str(
+            "[[holdings]]\nasset = \"ETH\"\nquantity = 2\ncost = 1500\n[[holdings]]\nasset = \"XYZ\"\nquantity = 1\n")?;
+        let infos = vec![Info::trading("ETH", "USDT", 1)];
+        let now: HashMap<Symbol, Decimal64> = [(Symbol::from("ETHUSDT"), Decimal64::from(1000))].iter().cloned().collect();
+        let then: HashMap<Symbol, Decimal64> = [(Symbol::from("ETHUSDT"), Decimal64::from(900))].iter().cloned().collect();
+        let positions = portfolio.valuate(&infos, |s| now.get(s).cloned(), |s| then.get(s).cloned());
+        assert_eq!(positions[0].value(), Some(2000.0));
+        assert_eq!(positions[0].pnl_24h(), Some(200.0));
+        assert_eq!(positions[0].pnl(), Some(500.0));
+        assert_eq!(positions[1].value(), None);
+        assert_eq!(positions[1].pnl(), None);
+        Ok(())
+    }
+}
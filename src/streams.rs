@@ -0,0 +1,48 @@
+//! Websocket streams that are started and stopped on demand by the `UI`
+use crate::utils::*;
+use crate::ui::Msg;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::connect_async;
+use futures_util::StreamExt;
+use url::Url;
+
+/// Binance combined stream endpoint
+const URI_WS_COMBINED: &str = "wss://stream.binance.com:9443/stream?streams=";
+
+/// Aggregated trades stream for one or more symbols.
+///
+/// The raw messages are wrapped with `wrap` and sent to the `UI`, which parses them with `parse_agg_trade`.
+/// The stream ends when the websocket closes or when the task is aborted.
+///
+/// See: https://binance-docs.github.io/apidocs/spot/en/#aggregate-trade-streams
+pub async fn ws_agg_trades(symbols: Vec<Symbol>, ui_tx: UnboundedSender<Msg>, wrap: fn(String) -> Msg)
+    -> Result<(), String> {
+    if symbols.is_empty() { return Ok(()); }
+    let streams: Vec<String> = symbols.iter().map(|s| format!("{}@aggTrade", s.to_lowercase())).collect();
+    let uri = format!("{}{}", URI_WS_COMBINED, streams.join("/"));
+    let uri: Url = Url::parse(&uri).map_err(|e| format!("Bad url: {:?}", e))?;
+    let (ws_stream, _) = match connect_async(uri).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            ui_tx.send(Msg::Msg(format!("Error connecting to trade stream: {:?}", e)))
+                 .map_err(|e| format!("UI failed: {:?}", e))?;
+            return Ok(());
+        }
+    };
+    let (_, mut read) = ws_stream.split();
+    while let Some(msg) = read.next().await {
+        match msg {
+            Ok(msg) => {
+                if msg.is_text() {
+                    ui_tx.send(wrap(msg.to_string())).map_err(|e| format!("UI failed: {:?}", e))?;
+                }
+            },
+            Err(e) => {
+                ui_tx.send(Msg::Msg(format!("Trade stream error: {:?}", e)))
+                     .map_err(|e| format!("UI failed: {:?}", e))?;
+                return Err(format!("Websocket error: {:?}", e));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,236 @@
+//! Colour themes.
+//!
+//! The theme is chosen once at start-up with `theme = "<name>"` in the config file. Built-in themes
+//! are `dark` (the default), `light`, `high-contrast` and `colorblind` (blue/orange instead of
+//! green/red). If the `NO_COLOR` environment variable is set, no colours are used at all.
+//!
+//! Custom themes change some colours of a built-in theme, for example
+//! ```toml
+//! theme = "mine"
+//! [themes.mine]
+//! base = "light"
+//! up = "#007700"
+//! down = "magenta"
+//! ```
+//! Colours are names like `red` or `lightblue`, `#rrggbb`, or a 256-colour index like `208`.
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tui::style::{Color, Style};
+
+/// The colours of all widgets
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub up: Color,          // rising prices, buys, bids
+    pub down: Color,        // falling prices, sells, asks
+    pub warn: Color,        // warnings, e.g. medium latency
+    pub muted: Color,       // missing data and secondary text
+    pub accent: Color,      // price chart line, about page
+    pub cursor: Color,      // cursor in the symbol selection
+    pub crosshair: Color,   // crosshair in the price chart
+    pub text: Color,        // axes, help text
+    pub background: Color,  // help background
+}
+
+/// Names of the built-in themes
+pub const THEMES: [&str; 4] = ["dark", "light", "high-contrast", "colorblind"];
+
+impl Default for Theme {
+    fn default() -> Self { Theme::dark() }
+}
+
+impl Theme {
+    /// The original colours, for dark terminals
+    pub fn dark() -> Theme {
+        Theme { up: Color::Green, down: Color::Red, warn: Color::Yellow, muted: Color::Gray, accent: Color::Cyan,
+                cursor: Color::Red, crosshair: Color::Yellow, text: Color::White, background: Color::Black }
+    }
+    /// For light terminals
+    pub fn light() -> Theme {
+        Theme { up: Color::Green, down: Color::Red, warn: Color::Indexed(130), muted: Color::DarkGray,
+                accent: Color::Blue, cursor: Color::Red, crosshair: Color::Magenta, text: Color::Black,
+                background: Color::White }
+    }
+    /// Bright colours only
+    pub fn high_contrast() -> Theme {
+        Theme { up: Color::LightGreen, down: Color::LightRed, warn: Color::LightYellow, muted: Color::White,
+                accent: Color::LightCyan, cursor: Color::LightMagenta, crosshair: Color::LightYellow,
+                text: Color::White, background: Color::Black }
+    }
+    /// Blue/orange instead of green/red, from the Okabe-Ito palette
+    pub fn colorblind() -> Theme {
+        Theme { up: Color::Rgb(0, 114, 178), down: Color::Rgb(230, 159, 0), warn: Color::Rgb(240, 228, 66),
+                muted: Color::Gray, accent: Color::Rgb(86, 180, 233), cursor: Color::Rgb(230, 159, 0),
+                crosshair: Color::Rgb(240, 228, 66), text: Color::White, background: Color::Black }
+    }
+    /// The terminal's default colours everywhere, for `NO_COLOR`
+    pub fn no_color() -> Theme {
+        Theme { up: Color::Reset, down: Color::Reset, warn: Color::Reset, muted: Color::Reset, accent: Color::Reset,
+                cursor: Color::Reset, crosshair: Color::Reset, text: Color::Reset, background: Color::Reset }
+    }
+    /// Built-in theme `name`
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark"          => Some(Theme::dark()),
+            "light"         => Some(Theme::light()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "colorblind"    => Some(Theme::colorblind()),
+            _ => None,
+        }
+    }
+    /// Theme `name`, built-in or defined in `custom`
+    pub fn new(name: &str, custom: &HashMap<String, HashMap<String, String>>) -> Result<Theme, String> {
+        if let Some(theme) = Theme::builtin(name) { return Ok(theme); }
+        let colours = custom.get(name).ok_or_else(|| format!("Unknown theme {:?}, use one of {} or define it",
+                                                              name, THEMES.join(", ")))?;
+        let base = colours.get("base").map(|s| s.as_str()).unwrap_or("dark");
+        let mut theme = Theme::builtin(base).ok_or_else(|| format!("Unknown base theme {:?}", base))?;
+        for (field, colour) in colours.iter().filter(|(field, _)| *field != "base") {
+            let colour = parse_color(colour)?;
+            *theme.field_mut(field).ok_or_else(|| format!("Unknown theme colour {:?}", field))? = colour;
+        }
+        Ok(theme)
+    }
+    /// The colour called `name`
+    fn field_mut(&mut self, name: &str) -> Option<&mut Color> {
+        Some(match name {
+            "up"         => &mut self.up,
+            "down"       => &mut self.down,
+            "warn"       => &mut self.warn,
+            "muted"      => &mut self.muted,
+            "accent"     => &mut self.accent,
+            "cursor"     => &mut self.cursor,
+            "crosshair"  => &mut self.crosshair,
+            "text"       => &mut self.text,
+            "background" => &mut self.background,
+            _ => return None,
+        })
+    }
+    pub fn up_style(&self) -> Style { Style::default().fg(self.up) }
+    pub fn down_style(&self) -> Style { Style::default().fg(self.down) }
+    pub fn muted_style(&self) -> Style { Style::default().fg(self.muted) }
+}
+
+/// Parse a colour: a name, `#rrggbb` or a 256-colour index
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let s = s.trim().to_lowercase();
+    if let Some(hex) = s.strip_prefix('#') {
+        let rgb = u32::from_str_radix(hex, 16).map_err(|_| format!("Bad colour {:?}", s))?;
+        if hex.len() != 6 { return Err(format!("Bad colour {:?}, use #rrggbb", s)); }
+        return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+    if let Ok(ix) = s.parse::<u8>() { return Ok(Color::Indexed(ix)); }
+    Ok(match s.replace(['-', '_', ' '], "").as_str() {
+        "reset" | "default" => Color::Reset,
+        "black"        => Color::Black,
+        "red"          => Color::Red,
+        "green"        => Color::Green,
+        "yellow"       => Color::Yellow,
+        "blue"         => Color::Blue,
+        "magenta"      => Color::Magenta,
+        "cyan"         => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred"     => Color::LightRed,
+        "lightgreen"   => Color::LightGreen,
+        "lightyellow"  => Color::LightYellow,
+        "lightblue"    => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan"    => Color::LightCyan,
+        "white"        => Color::White,
+        _ => return Err(format!("Unknown colour {:?}", s)),
+    })
+}
+
+/// `color` as red, green and blue, named colours as in xterm. `None` for `Reset` and indexed colours.
+pub fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    Some(match color {
+        Color::Rgb(r, g, b)   => (r, g, b),
+        Color::Black          => (0, 0, 0),
+        Color::Red            => (205, 0, 0),
+        Color::Green          => (0, 205, 0),
+        Color::Yellow         => (205, 205, 0),
+        Color::Blue           => (0, 0, 238),
+        Color::Magenta        => (205, 0, 205),
+        Color::Cyan           => (0, 205, 205),
+        Color::Gray           => (229, 229, 229),
+        Color::DarkGray       => (127, 127, 127),
+        Color::LightRed       => (255, 0, 0),
+        Color::LightGreen     => (0, 255, 0),
+        Color::LightYellow    => (255, 255, 0),
+        Color::LightBlue      => (92, 92, 255),
+        Color::LightMagenta   => (255, 0, 255),
+        Color::LightCyan      => (0, 255, 255),
+        Color::White          => (255, 255, 255),
+        Color::Reset | Color::Indexed(_) => return None,
+    })
+}
+
+/// `color` darkened towards black, `intensity` 1 keeps it, 0 is black. Colours without `rgb` are kept.
+pub fn shade(color: Color, intensity: f64) -> Color {
+    match rgb(color) {
+        Some((r, g, b)) => {
+            let f = intensity.clamp(0.0, 1.0);
+            Color::Rgb((r as f64 * f) as u8, (g as f64 * f) as u8, (b as f64 * f) as u8)
+        },
+        None => color,
+    }
+}
+
+/// Black or white, whichever is easier to read on `color`. `Reset` for colours without `rgb`.
+pub fn contrast(color: Color) -> Color {
+    match rgb(color) {
+        Some((r, g, b)) if 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64 > 128.0 => Color::Black,
+        Some(_) => Color::White,
+        None => Color::Reset,
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Set the theme, only the first call has an effect
+pub fn init(theme: Theme) {
+    THEME.set(theme).unwrap_or(());
+}
+
+/// The current theme, `dark` if `init` wasn't called
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(Theme::dark)
+}
+
+/// Is colour output disabled by the `NO_COLOR` environment variable? See <https://no-color.org>
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#ff8000"), Ok(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("Light-Blue"), Ok(Color::LightBlue));
+        assert_eq!(parse_color("208"), Ok(Color::Indexed(208)));
+        assert!(parse_color("#fff").is_err());
+        assert!(parse_color("ultraviolet").is_err());
+        assert_eq!(shade(Color::Rgb(200, 100, 0), 0.5), Color::Rgb(100, 50, 0));
+        assert_eq!(shade(Color::Reset, 0.5), Color::Reset);
+        assert_eq!(contrast(Color::Yellow), Color::Black);
+        assert_eq!(contrast(shade(Color::Green, 0.3)), Color::White);
+    }
+
+    #[test]
+    fn custom_themes() {
+        let mut custom: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mine: HashMap<String, String> = [("base", "light"), ("up", "#007700")].iter()
+            .map(|(k, v)| (String::from(*k), String::from(*v))).collect();
+        custom.insert(String::from("mine"), mine);
+        let theme = Theme::new("mine", &custom).unwrap();
+        assert_eq!(theme.up, Color::Rgb(0, 119, 0));
+        assert_eq!(theme.down, Theme::light().down);
+        assert_eq!(Theme::new("colorblind", &custom), Ok(Theme::colorblind()));
+        assert!(Theme::new("nope", &custom).is_err());
+        custom.get_mut("mine").unwrap().insert(String::from("sparkle"), String::from("red"));
+        assert!(Theme::new("mine", &custom).is_err());
+    }
+}
@@ -15,37 +15,80 @@ pub mod graph;
 pub mod search;
 /// Pretty printing of floats and Decimal
 pub mod nice;
+/// The trades (time and sales) page
+pub mod trades;
+/// The large trades page
+pub mod large_trades;
+/// The symbol detail page
+pub mod detail;
+/// The filter rules page
+pub mod filters;
+/// The portfolio page
+pub mod positions;
+/// The triangular arbitrage page
+pub mod cycles;
+/// The heatmap page
+pub mod heatmap;
+/// The top movers page
+pub mod movers;
 
 use crate::utils::*;
+use crate::config::Config;
+use crate::keys::KeyMap;
+use crate::theme;
+use crate::portfolio::Portfolio;
+use crate::arbitrage::{self, Cycle};
+use crate::history::TickHistory;
+use crate::cache::{self, KlineCache};
+use crate::filter;
+use crate::fuzzy;
+use crate::streams::ws_agg_trades;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tui::{
     backend::Backend,
-    style::{Style, Color, Modifier},
+    style::{Style, Modifier},
     widgets::{Paragraph},
     layout::{Layout, Constraint, Direction, Rect, Alignment},
     text::{Span, Spans},
     terminal::Frame,
 };
 use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use termion::event::{Key, MouseButton, MouseEvent};
 use std::collections::HashMap;
 use chrono::Local;
 use std::marker::Copy;
 use dec::Decimal64;
 use inlinable_string::{InlineString};
 
+/// Minimum time between two re-sorts of the price list, so the screen does not jitter
+const SORT_INTERVAL_MILLIS: u64 = 3000;
+
 /// Stores the relevant market data with some extra rendering information
 pub struct MarketState {
     px: Decimal64,
     ts: u64,
     last_px: Decimal64,
     px_24h: Decimal64,
+    open: Decimal64,
+    high: Decimal64,
+    low: Decimal64,
+    volume: Decimal64,
+    quote_volume: Decimal64,
+    n_trades: u64,
+    bid: Decimal64,
+    ask: Decimal64,
 }
 
 impl MarketState {
     /// Create new `MarketState` with NANs.
     fn new() -> Self {
-        MarketState { px: Decimal64::NAN, ts: 0, last_px: Decimal64::NAN, px_24h:Decimal64::NAN }
+        MarketState { px: Decimal64::NAN, ts: 0, last_px: Decimal64::NAN, px_24h:Decimal64::NAN,
+                      open: Decimal64::NAN, high: Decimal64::NAN, low: Decimal64::NAN,
+                      volume: Decimal64::NAN, quote_volume: Decimal64::NAN, n_trades: 0,
+                      bid: Decimal64::NAN, ask: Decimal64::NAN }
     }
     /// Update `MarketState` with data from `Update`
     fn update(self: &mut Self, update: &Update) {
@@ -53,6 +96,14 @@ impl MarketState {
         self.px = update.px;
         self.px_24h = update.px_24h;
         self.ts = update.ts;
+        self.open = update.open;
+        self.high = update.high;
+        self.low = update.low;
+        self.volume = update.volume;
+        self.quote_volume = update.quote_volume;
+        self.n_trades = update.n_trades;
+        self.bid = update.bid;
+        self.ask = update.ask;
     }
     /// Make a nicely formatted price string
     pub fn price_string(self: &Self) -> String {
@@ -73,12 +124,16 @@ impl MarketState {
         s.truncate(6);
         format!("{:>6}", s)
     } 
+    /// Relative 24h change, NAN if unknown
+    pub fn change(&self) -> Decimal64 {
+        (self.px-self.px_24h)/self.px_24h
+    }
     /// Generate a style for this price
     pub fn style(self: &Self) -> Style {
         if self.px > self.last_px {
-            Style::default().fg(Color::Green)
+            theme::current().up_style()
         } else if self.px < self.last_px {
-            Style::default().fg(Color::Red)
+            theme::current().down_style()
         } else {
             Style::default()
         }
@@ -86,41 +141,139 @@ impl MarketState {
     /// Generate a style for this percentage
     pub fn style_percent(self: &Self) -> Style {
         if self.px > self.px_24h {
-            Style::default().fg(Color::Green)
+            theme::current().up_style()
         } else if self.px < self.px_24h {
-            Style::default().fg(Color::Red)
+            theme::current().down_style()
         } else {
             Style::default()
         }
     }
 }
 
+/// Frames of the loading indicator
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Kline interval of the graph time scale `0`..`9`
+fn interval(time_scale: u32) -> Interval {
+    match time_scale {
+        1 => Interval::I5m,
+        2 => Interval::I15m,
+        3 => Interval::I30m,
+        4 => Interval::I1h,
+        5 => Interval::I2h,
+        6 => Interval::I4h,
+        7 => Interval::I8h,
+        8 => Interval::I12h,
+        9 => Interval::I1d,
+        _ => Interval::I1m,
+    }
+}
+
+/// Klines of `symbol`: the cached ones followed by the bars downloaded since, the cached ones alone if
+/// the download fails. Also returns the message for the message bar. Blocking, both the download and the
+/// cache are read and written synchronously.
+///
+/// `None` if `cancelled` is set before the download or before the result is merged into the cache,
+/// a request in flight can't be interrupted but times out after `HTTP_TIMEOUT`.
+fn load_klines(cache: &KlineCache, symbol: &Symbol, interval: &Interval, cancelled: &AtomicBool)
+               -> Option<Result<(Vec<Bar>, String), String>> {
+    if cancelled.load(Ordering::Relaxed) { return None; }
+    let cached = cache.load(symbol, interval);
+    let since = cache::missing_since(&cached, interval, now_timestamp());
+    let fresh = get_klines(symbol, interval, since).map_err(|e| e.to_string());
+    if cancelled.load(Ordering::Relaxed) { return None; }
+    Some(match fresh {
+        Ok(fresh) => {
+            let n_fresh = fresh.len();
+            let klines = if since.is_some() { cache::merge(cached, fresh) } else { fresh };
+            let message = match cache.store(symbol, interval, &klines) {
+                Ok(()) if cache.is_enabled() => format!("Show {} klines for {} ({} downloaded)", interval.str(), symbol, n_fresh),
+                Ok(()) => format!("Show {} klines for {}", interval.str(), symbol),
+                Err(e) => e,
+            };
+            Ok((klines, message))
+        },
+        Err(e) if !cached.is_empty() => {
+            Ok((cached, format!("Show cached {} klines for {}, download failed: {}", interval.str(), symbol, e)))
+        },
+        Err(e) => Err(format!("Failed to get klines: {}", e)),
+    })
+}
+
+/// Currencies the price list can be shown in, cycled by `Msg::Currency` after the quote of each symbol
+const CURRENCIES: [&str; 4] = ["USDT", "BTC", "ETH", "EUR"];
+
+/// Columns the price list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Volume, // 24h volume in USDT
+    Change, // 24h % change
+    Price,  // price in USDT
+    Name,   // short symbol
+}
+
+impl SortKey {
+    /// Name for the message bar
+    pub fn str(&self) -> &str {
+        match self {
+            SortKey::Volume => "volume",
+            SortKey::Change => "change",
+            SortKey::Price  => "price",
+            SortKey::Name   => "name",
+        }
+    }
+}
+
 /// Messages that the `UI` can receive
 #[derive(Debug)]
 pub enum Msg {
-    WS(u64, String),    // timestamp (millis) and websocket data
+    WS(u64, String),    // timestamp (millis) and websocket data that are not updates, e.g. pings
+    Updates(Vec<Update>), // updates of one websocket message, parsed by the network task
     Infos(Vec<Info>),   // Downloaded infos for each symbol
+    PartialInfos(Vec<Info>), // infos before their volumes are known, sorted by name
     Msg(String),        // info message to UI
     PriceList,          // On 'l' key press show PriceList
     PriceTable,         // On 't' key press show PriceTable
     Graph(Option<u32>), // On 'g' display graph with given time scale, or stored time scale if Nothing
+    Klines(Symbol, u32, Result<(Vec<Bar>, String), String>), // klines of a symbol and time scale, and the message
     TogglePercent,      // On '%' key press
     ToggleExtended,     // On 'x' key press
     Search,             // On 's' show the search widget
+    Trades,             // On 'r' show the trades of the selected symbol
+    TradeWS(String),    // aggregated trade stream data of the selected symbol
+    LargeTrades,        // On 'w' show the large trades
+    Detail,             // On 'd' show the 24h statistics of the selected symbol
+    Sort(SortKey),      // On 'v', 'c', 'p', 'n' sort price list, pressing again reverses the order
+    Filter,             // On 'f' show the filter rules
+    Portfolio,          // On 'o' show the portfolio
+    Arbitrage,          // On 'b' show the triangular arbitrage cycles
+    Heatmap,            // On 'm' show the market heatmap
+    Movers,             // On 'u' show the top movers
+    Currency,           // On '$' cycle the currency of the price list
+    Add,                // On '+' add an item (filter rule)
+    Delete,             // On '-' or Delete delete the selected item (filter rule)
+    Input(Key),         // Key press while text is being entered
+    Mouse(MouseEvent),  // Mouse click or scroll wheel
+    LargeTradeWS(String), // aggregated trade stream data of the symbols monitored for large trades
     ArrowUp,            // On arrow up
     ArrowDown,          // On arrow down
     ArrowLeft,          // On srrow left
     ArrowRight,         // On arrow right
     Home,               // Home Home key reset cursor to top left
+    PageUp,             // On page up
+    PageDown,           // On page down
     Enter,              // On pressing enter
-    Help,               // On 'h' key press show help
+    Help,               // On 'h' or '?' toggle the help overlay
     About,              // On 'a' key press show about page
     Esc,                // On ESC go back to previous page
     Stop                // stop ui
 }
 
 /// Just tui::Terminal<...>
-type Term = tui::Terminal<tui::backend::TermionBackend<termion::raw::RawTerminal<std::io::Stdout>>>;
+type Term = tui::Terminal<tui::backend::TermionBackend<termion::input::MouseTerminal<termion::raw::RawTerminal<std::io::Stdout>>>>;
+
+/// Clickable cells reported by the widgets: screen area and cursor position of the cell
+pub type Hits = Vec<(Rect, (usize, usize))>;
 
 /// All the different pages
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -129,8 +282,15 @@ enum UIView {
     PriceTable, // display PriceTable
     Graph,      // display graph
     Search,     // display search widget
+    Trades,     // display trades of the selected symbol
+    LargeTrades,// display large trades
+    Detail,     // display 24h statistics of the selected symbol
+    Filter,     // display filter rules
+    Portfolio,  // display portfolio valuation
+    Arbitrage,  // display triangular arbitrage cycles
+    Heatmap,    // display market heatmap
+    Movers,     // display top gainers, losers and most active symbols
     Empty,      // display PriceTable
-    Help,       // display help
     About,      // display help
 }
 
@@ -141,6 +301,7 @@ impl Copy for UIView { }
 pub struct UIState {
     message: String,
     markets: HashMap<Symbol, MarketState>,
+    history: TickHistory,               // recent prices of each symbol
     latency: u64,
     ui_mode: UIView,
     ui_mode_back: Option<UIView>,       // where to go back to if ESC is pressed
@@ -148,20 +309,51 @@ pub struct UIState {
     extended: bool,                     // extended view of table page
     ts_last_update: u64,                // ts of last market update
     lookup: Option<HashMap<Symbol, Info>>,
-    infos: Option<Vec<Info>>,
+    all_infos: Option<Vec<Info>>,       // all symbols sorted by volume
+    infos: Option<Vec<Info>>,           // `all_infos` that pass the filter rules
     klines: Option<Vec<Bar>>,
+    kline_cache: KlineCache,            // klines on disk, so that only new bars are downloaded
+    loading: Option<(Symbol, u32, u64)>,  // symbol, time scale and start ts of the klines being downloaded
+    klines_cancel: Option<Arc<AtomicBool>>, // cancels the blocking download of the klines
     symbol: Symbol,
     time_scale: u32,                    // time scale for graph
     cursor_ix: u16,                     // x position of symbol in search widget
     cursor_iy: u16,                     // y position of symbol in search widget
+    search_scroll: (u16, u16),          // first visible column and number of visible columns in search widget
+    list_offset: usize,                 // index of first symbol shown in price list
+    list_shown: (usize, usize),         // height and number of symbols shown in price list
+    table_cursor: (usize, usize),       // index of selected base and quote in price table
+    table_symbol: Option<Symbol>,       // symbol at the price table cursor, `None` if not traded
+    hits: Hits,                         // clickable cells of the current view
+    crosshair: Option<u16>,             // screen column of the graph crosshair
+    help: Option<UIView>,               // view whose help overlay is shown
+    tape: Option<trades::TradeTape>,    // recent trades of the selected symbol
+    tape_task: Option<tokio::task::JoinHandle<Result<(), String>>>, // aggTrade stream feeding `tape`
+    config: Config,
+    config_error: Option<String>,       // why the config file could not be loaded, it is not saved then
+    keys: KeyMap,                       // key bindings, for the help page
+    large: large_trades::LargeTradeDetector,
+    large_symbols: usize,               // number of symbols monitored for large trades
+    large_task: Option<tokio::task::JoinHandle<Result<(), String>>>, // aggTrade stream feeding `large`
+    list_infos: Vec<Info>,              // `infos` in price list order
+    sort_key: SortKey,                  // price list order
+    sort_desc: bool,                    // price list order descending?
+    ts_last_sort: u64,                  // ts of last sort of `list_infos`
+    filter_cursor: usize,               // index of selected rule in filter page
+    portfolio: Portfolio,               // holdings, reloaded when the portfolio page is shown
+    cycles: Vec<Cycle>,                 // triangular arbitrage cycles of `infos`
+    input: Option<String>,              // text being entered, if any
+    input_mode: Arc<AtomicBool>,        // tells `listen_keys` to send `Msg::Input`
 }
 
 impl UIState {
     /// New `UIState` with empty fields, 0 latency, ui_mode `PriceList`
-    fn new() -> Self {
+    fn new(config: Config, config_error: Option<String>, keys: KeyMap, kline_cache: KlineCache,
+           input_mode: Arc<AtomicBool>) -> Self {
         UIState { 
             message: String::new(), 
             markets: HashMap::new(),
+            history: TickHistory::new(&config.history),
             latency: 0,
             ui_mode: UIView::Empty,
             ui_mode_back: None,
@@ -169,52 +361,237 @@ impl UIState {
             extended: true,
             ts_last_update: 0,
             lookup: None,
+            all_infos: None,
             infos: None,
             klines: None,
+            kline_cache,
+            loading: None,
+            klines_cancel: None,
             symbol: InlineString::from("BTCUSDT"),
             time_scale: 0,
             cursor_ix: 0,
             cursor_iy: 0,
+            search_scroll: (0, 1),
+            list_offset: 0,
+            list_shown: (1, 1),
+            table_cursor: (0, 0),
+            table_symbol: None,
+            hits: Vec::new(),
+            crosshair: None,
+            help: None,
+            tape: None,
+            tape_task: None,
+            large: large_trades::LargeTradeDetector::new(&config.large_trades),
+            large_symbols: 0,
+            large_task: None,
+            list_infos: Vec::new(),
+            sort_key: SortKey::Volume,
+            sort_desc: true,
+            ts_last_sort: 0,
+            filter_cursor: 0,
+            portfolio: Portfolio::default(),
+            cycles: Vec::new(),
+            input: None,
+            input_mode,
+            config,
+            config_error,
+            keys,
         }
     }
-    fn update(self: &mut Self, updates: &Vec<Update>) {
+    fn update(&mut self, updates: &[Update]) {
         if let Some(lookup) = &self.lookup {
             for u in updates {
                 if u.ts > self.ts_last_update { self.ts_last_update = u.ts; }
                 let info = lookup.get(&u.symbol);
                 if let Some(_) = info {
                     self.markets.entry(u.symbol.clone()).or_insert(MarketState::new()).update(&u);
+                    self.history.push(&u.symbol, u.ts, dec_to_f64(u.px));
                 }
             }
         }
     }
+    /// Apply the filter rules to `all_infos`, re-sort the price list
+    fn apply_filters(&mut self) {
+        if self.all_infos.is_some() {
+            self.refilter(true);
+            self.list_offset = 0;
+            self.cursor_ix = 0;
+            self.cursor_iy = 0;
+        }
+    }
+    /// Apply the filter rules to `all_infos` and re-sort the price list, keeping the scroll position.
+    /// The arbitrage cycles are rebuilt if `cycles`.
+    fn refilter(&mut self, cycles: bool) {
+        if let Some(all_infos) = &self.all_infos {
+            let infos = filter::apply(&self.config.filters, all_infos);
+            if cycles { self.cycles = arbitrage::cycles(&infos); }
+            self.list_offset = self.list_offset.min(infos.len().saturating_sub(1));
+            self.infos = Some(infos);
+            self.list_infos.clear();
+            self.sort_list(true);
+        }
+    }
+    /// Apply the filter rules and save them in the config file
+    fn filters_changed(&mut self) {
+        self.apply_filters();
+        self.save_config(&["filters"]);
+    }
+    /// Save `fields` in the config file, unless it could not be loaded, so that a broken file is not
+    /// overwritten with the defaults
+    fn save_config(&mut self, fields: &[&str]) {
+        let result = match &self.config_error {
+            Some(e) => Err(format!("Not saving config: {}", e)),
+            None => self.config.save(fields),
+        };
+        if let Err(e) = result { self.message = e; }
+    }
+    /// Scroll the price list forward by `n` symbols, but not beyond the last one.
+    ///
+    /// The columns are as high as the list area, so the list only ever scrolls sideways and paging
+    /// by rows would not show any other symbols.
+    fn scroll_list(&mut self, n: usize) {
+        let len = self.list_infos.len();
+        if self.list_offset + n < len { self.list_offset += n; }
+    }
+    /// Move the price list cursor (the selected symbol) by `n`, scroll by whole columns to keep it visible
+    fn move_list_cursor(&mut self, n: isize) {
+        let len = self.list_infos.len();
+        if len == 0 { return; }
+        let ix = self.list_infos.iter().position(|info| info.symbol == self.symbol).unwrap_or(self.list_offset);
+        let ix = (ix as isize + n).clamp(0, len as isize - 1) as usize;
+        self.symbol = self.list_infos[ix].symbol.clone();
+        let (height, shown) = (self.list_shown.0.max(1), self.list_shown.1.max(1));
+        while ix < self.list_offset { self.list_offset = self.list_offset.saturating_sub(height); }
+        while ix >= self.list_offset + shown { self.list_offset += height; }
+    }
+    /// Start entering text, keys are now sent as `Msg::Input`
+    fn start_input(&mut self) {
+        self.input = Some(String::new());
+        self.input_mode.store(true, Ordering::Relaxed);
+    }
+    /// Stop entering text and return it
+    fn stop_input(&mut self) -> Option<String> {
+        self.input_mode.store(false, Ordering::Relaxed);
+        self.input.take()
+    }
+    /// Cancel the download of the klines, if any
+    fn cancel_klines(&mut self) {
+        if let Some(cancel) = self.klines_cancel.take() { cancel.store(true, Ordering::Relaxed); }
+        self.loading = None;
+    }
+    /// Sort `list_infos` by `sort_key`, at most every `SORT_INTERVAL_MILLIS` unless `force`d.
+    ///
+    /// The sort is stable and starts from the previous order, so equal items don't move.
+    /// Unknown (NAN) values always go to the end.
+    fn sort_list(&mut self, force: bool) {
+        let now = now_timestamp();
+        if !force && now < self.ts_last_sort + SORT_INTERVAL_MILLIS { return; }
+        self.ts_last_sort = now;
+        let infos = match &self.infos {
+            Some(infos) => infos,
+            None => return,
+        };
+        if self.list_infos.len() != infos.len() { self.list_infos = infos.clone(); }
+        let (key, desc) = (self.sort_key, self.sort_desc);
+        if key == SortKey::Name {
+            self.list_infos.sort_by(|a, b| {
+                let o = a.short_symbol().cmp(b.short_symbol());
+                if desc { o.reverse() } else { o }
+            });
+            return;
+        }
+        let markets = &self.markets;
+        let price = |s: &Symbol| markets.get(s).map(|m| m.px);
+        let mut keyed: Vec<(Decimal64, Info)> = self.list_infos.drain(..).map(|info| {
+            let mkt = markets.get(&info.symbol);
+            let k = match key {
+                SortKey::Volume => mkt.and_then(|m| to_usdt(m.quote_volume, &info.quote, price)).unwrap_or(info.volume),
+                SortKey::Change => mkt.map(|m| m.change()).unwrap_or(Decimal64::NAN),
+                SortKey::Price  => mkt.and_then(|m| to_usdt(m.px, &info.quote, price)).unwrap_or(Decimal64::NAN),
+                SortKey::Name   => Decimal64::NAN,
+            };
+            (k, info)
+        }).collect();
+        keyed.sort_by(|(a, _), (b, _)| {
+            match (a.is_nan(), b.is_nan()) {
+                (true, true)   => std::cmp::Ordering::Equal,
+                (true, false)  => std::cmp::Ordering::Greater,
+                (false, true)  => std::cmp::Ordering::Less,
+                (false, false) => {
+                    let o = a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal);
+                    if desc { o.reverse() } else { o }
+                }
+            }
+        });
+        self.list_infos = keyed.into_iter().map(|(_, info)| info).collect();
+    }
 }
 /// Encapsulates the `UI`
 pub struct UI {
     pub tx: UnboundedSender<Msg>,
     pub handle: tokio::task::JoinHandle<()>,
+    pub input_mode: Arc<AtomicBool>,    // true while text is being entered
 }
 
 impl UI {
     /// Create new `UI`
-    pub fn new(mut terminal: Term) -> Self {
+    pub fn new(mut terminal: Term, config: Config, config_error: Option<String>, keys: KeyMap,
+               kline_cache: KlineCache) -> Self {
         terminal.clear().expect("Terminal failed!");
         let (tx, mut rx) = unbounded_channel();
+        let ui_tx = tx.clone();     // for streams started by the UI
+        let input_mode = Arc::new(AtomicBool::new(false));
+        let state_input_mode = input_mode.clone();
         let handle = tokio::spawn( async move {
-            let mut state = UIState::new();
-            let mut buf: Vec<Update> = Vec::with_capacity(2000);    // buffer for parse_updates
+            let mut state = UIState::new(config, config_error, keys, kline_cache, state_input_mode);
             let mut cursor_moved: bool = false;                     // used for setting message after draw is done
             while let Some(msg) = rx.recv().await {
+                // While typing in the search page, keys that don't edit the query navigate the matches
+                let msg = match msg {
+                    Msg::Input(Key::F(1)) => Msg::Help,                                 // help while typing
+                    Msg::Input(Key::Esc) if state.help == Some(state.ui_mode) => Msg::Esc,  // close help, keep typing
+                    Msg::Input(key) if state.ui_mode == UIView::Search => match key {
+                        Key::Up         => Msg::ArrowUp,
+                        Key::Down       => Msg::ArrowDown,
+                        Key::Left       => Msg::ArrowLeft,
+                        Key::Right      => Msg::ArrowRight,
+                        Key::Home       => Msg::Home,
+                        Key::PageUp     => Msg::PageUp,
+                        Key::PageDown   => Msg::PageDown,
+                        Key::Char('\n') => { state.stop_input(); Msg::Enter },
+                        Key::Esc        => { state.stop_input(); Msg::Esc },
+                        // Alt-<key> runs the command of <key>, keys bound with Ctrl or Alt run theirs,
+                        // e.g. M-r shows the trades of the selected symbol
+                        Key::Ctrl(_) | Key::Alt(_) => {
+                            let plain = if let Key::Alt(c) = key { state.keys.get(&Key::Char(c)) } else { None };
+                            state.keys.get(&key).or(plain).and_then(|action| action.msg()).unwrap_or(Msg::Input(key))
+                        },
+                        key             => Msg::Input(key),
+                    },
+                    msg => msg,
+                };
                 match msg {
+                    Msg::PartialInfos(infos_) => {
+                        state.lookup = Some(infos_to_lookup(&infos_));
+                        state.all_infos = Some(infos_);
+                        state.refilter(false);
+                        if state.ui_mode == UIView::Empty { state.ui_mode = UIView::PriceList; }
+                    },
                     Msg::Infos(infos_) => {
-                        state.infos = Some(infos_.iter().cloned().filter(|i| i.quote != "TUSD" && i.quote != "BUSD" && i.quote != "USDC").collect());
+                        let partial = state.all_infos.is_some();
                         state.lookup = Some(infos_to_lookup(&infos_));
-                        state.ui_mode = UIView::PriceList;
+                        state.all_infos = Some(infos_);
+                        // after the partial infos the user may already be scrolling, and has left the splash screen
+                        if partial { state.refilter(true); } else { state.apply_filters(); }
+                        if state.ui_mode == UIView::Empty { state.ui_mode = UIView::PriceList; }
+                        UI::watch_large_trades(&mut state, &ui_tx);
+                    },
+                    Msg::Updates(us) => {
+                        state.update(&us);
+                        state.sort_list(false);
                     },
                     Msg::WS(ts_rec, msg) => {
-                        if let Ok(us) = parse_updates(&msg, &mut buf) {
-                            state.update(&us);
-                        } else if let Ok(ts) = msg.parse::<u64>() {
+                        if let Ok(ts) = msg.parse::<u64>() {
                             state.latency = ts_rec-ts;
                         } else {
                             state.message = format!("{:?}", msg);
@@ -234,14 +611,216 @@ impl UI {
                     },
                     Msg::Graph(scale) => {
                         state.time_scale = scale.unwrap_or(state.time_scale);
-                        UI::graph(&mut state, &mut terminal).await;
+                        UI::graph(&mut state, &ui_tx);
+                    },
+                    Msg::Klines(symbol, time_scale, klines) => {
+                        // ignore downloads that were superseded
+                        if state.loading.as_ref().map(|(s, t, _)| *s == symbol && *t == time_scale) != Some(true) { continue; }
+                        state.loading = None;
+                        state.klines_cancel = None;
+                        match klines {
+                            Ok((klines, message)) => {
+                                state.ui_mode = UIView::Graph;
+                                state.symbol = symbol;
+                                state.message = message;
+                                state.klines = Some(klines);
+                                state.crosshair = None;
+                            },
+                            Err(e) => { state.message = e; }
+                        }
                     },
                     Msg::Search => {
                         state.ui_mode_back = Some(state.ui_mode);
                         state.ui_mode = UIView::Search;
-                        state.message = String::from("Select symbol");
+                        state.cursor_ix = 0;
+                        state.cursor_iy = 0;
+                        state.search_scroll.0 = 0;
+                        state.start_input();
+                        state.message = String::from("Type to search, select with arrow keys and return");
+                    },
+                    Msg::Trades => {
+                        UI::trades(&mut state, &ui_tx);
+                    },
+                    Msg::TradeWS(msg) => {
+                        match parse_agg_trade(&msg) {
+                            Ok(trade) => {
+                                if let Some(tape) = &mut state.tape {
+                                    if tape.symbol == trade.symbol { tape.push(trade); }
+                                }
+                            },
+                            Err(e) => { state.message = format!("Bad trade message: {:?}", e); }
+                        }
+                        if state.ui_mode != UIView::Trades { continue; }  // nothing to redraw
+                    },
+                    Msg::Sort(key) => {
+                        state.sort_desc = if key == state.sort_key { !state.sort_desc } else { key != SortKey::Name };
+                        state.sort_key = key;
+                        state.sort_list(true);
+                        state.ui_mode = UIView::PriceList;
+                        state.message = format!("Sort price list by {}", key.str());
+                    },
+                    Msg::Filter => {
+                        state.ui_mode_back = Some(state.ui_mode);
+                        state.ui_mode = UIView::Filter;
+                        state.message = String::from("Filter rules");
+                    },
+                    Msg::Portfolio => {
+                        match Portfolio::load() {
+                            Ok(portfolio) => {
+                                state.message = format!("Portfolio with {} holdings", portfolio.holdings.len());
+                                state.portfolio = portfolio;
+                            },
+                            Err(e) => { state.message = e; }
+                        }
+                        state.ui_mode = UIView::Portfolio;
+                    },
+                    Msg::Heatmap => {
+                        state.ui_mode = UIView::Heatmap;
+                        state.message = String::from("Heatmap");
+                    },
+                    Msg::Movers => {
+                        state.ui_mode = UIView::Movers;
+                        state.message = String::from("Top movers");
+                    },
+                    Msg::Arbitrage => {
+                        state.ui_mode = UIView::Arbitrage;
+                        state.message = format!("{} triangular arbitrage cycles", state.cycles.len());
+                    },
+                    Msg::Add => {
+                        if state.ui_mode == UIView::Filter {
+                            state.start_input();
+                            state.message = String::from("Enter new rule");
+                        }
+                    },
+                    Msg::Delete => {
+                        if state.ui_mode == UIView::Filter && state.filter_cursor < state.config.filters.len() {
+                            let rule = state.config.filters.remove(state.filter_cursor);
+                            state.filter_cursor = state.filter_cursor.min(state.config.filters.len().saturating_sub(1));
+                            state.message = format!("Deleted rule {}", rule);
+                            state.filters_changed();
+                        }
+                    },
+                    Msg::Input(key) => {
+                        match key {
+                            Key::Char('\n') => {
+                                let input = state.stop_input().unwrap_or_default();
+                                if state.ui_mode == UIView::Filter {
+                                    match input.parse::<filter::Rule>() {
+                                        Ok(rule) => {
+                                            state.message = format!("Added rule {}", rule);
+                                            state.config.filters.push(rule);
+                                            state.filter_cursor = state.config.filters.len() - 1;
+                                            state.filters_changed();
+                                        },
+                                        Err(e) => { state.message = e; }
+                                    }
+                                }
+                            },
+                            Key::Esc => {
+                                state.stop_input();
+                                state.message = String::from("Cancelled");
+                            },
+                            Key::Char(c) => { if let Some(input) = &mut state.input { input.push(c); } },
+                            Key::Backspace => { if let Some(input) = &mut state.input { input.pop(); } },
+                            _ => {}
+                        }
+                        if state.ui_mode == UIView::Search {    // best match is at the top left
+                            state.cursor_ix = 0;
+                            state.cursor_iy = 0;
+                            cursor_moved = true;
+                        }
+                    },
+                    Msg::Detail => {
+                        state.ui_mode = UIView::Detail;
+                        state.message = format!("Details {}", state.symbol);
+                    },
+                    Msg::LargeTrades => {
+                        state.ui_mode = UIView::LargeTrades;
+                        state.message = String::from("Show large trades");
+                    },
+                    Msg::LargeTradeWS(msg) => {
+                        match parse_agg_trade(&msg) {
+                            Ok(trade) => {
+                                let quote = state.lookup.as_ref().and_then(|lookup| lookup.get(&trade.symbol)).map(|i| i.quote.clone());
+                                let markets = &state.markets;
+                                let usdt = quote.and_then(|q| to_usdt(trade.notional(), &q, |s| markets.get(s).map(|m| m.px)));
+                                let large = match usdt {
+                                    Some(usdt) => state.large.check(trade, usdt).map(|large| large.summary()),
+                                    None => None,
+                                };
+                                match large {
+                                    Some(summary) if state.config.large_trades.notify => { state.message = summary; },
+                                    _ => { if state.ui_mode != UIView::LargeTrades { continue; } }  // nothing to redraw
+                                }
+                            },
+                            Err(e) => { state.message = format!("Bad trade message: {:?}", e); }
+                        }
+                    },
+                    Msg::Mouse(mouse) => {
+                        match mouse {
+                            MouseEvent::Press(MouseButton::WheelUp, _, _) => {
+                                let msg = if state.ui_mode == UIView::PriceTable { Msg::ArrowUp } else { Msg::PageUp };
+                                ui_tx.send(msg).expect("UI failed");
+                                continue;
+                            },
+                            MouseEvent::Press(MouseButton::WheelDown, _, _) => {
+                                let msg = if state.ui_mode == UIView::PriceTable { Msg::ArrowDown } else { Msg::PageDown };
+                                ui_tx.send(msg).expect("UI failed");
+                                continue;
+                            },
+                            MouseEvent::Press(MouseButton::Left, x, y) => {
+                                let (x, y) = (x.saturating_sub(1), y.saturating_sub(1));   // termion counts from 1
+                                if state.ui_mode == UIView::Graph {
+                                    state.crosshair = Some(x);
+                                } else {
+                                    let cell = state.hits.iter()
+                                        .find(|(r, _)| x >= r.x && x < r.x + r.width && y >= r.y && y < r.y + r.height)
+                                        .map(|(_, cell)| *cell);
+                                    let cell = match cell { Some(cell) => cell, None => continue };
+                                    // a click selects, a click on the selected symbol shows the graph
+                                    let selected = match state.ui_mode {
+                                        UIView::PriceList => {
+                                            // the list may have shrunk since it was drawn
+                                            let symbol = match state.list_infos.get(cell.0) { Some(info) => info.symbol.clone(), None => continue };
+                                            std::mem::replace(&mut state.symbol, symbol) == state.symbol
+                                        },
+                                        UIView::PriceTable => std::mem::replace(&mut state.table_cursor, cell) == cell,
+                                        UIView::Heatmap => {
+                                            let symbol = match state.infos.as_ref().and_then(|infos| infos.get(cell.0)) {
+                                                Some(info) => info.symbol.clone(),
+                                                None => continue,
+                                            };
+                                            std::mem::replace(&mut state.symbol, symbol) == state.symbol
+                                        },
+                                        UIView::Search => {
+                                            let cursor = (cell.0 as u16, cell.1 as u16);
+                                            let selected = (state.cursor_ix, state.cursor_iy) == cursor;
+                                            state.cursor_ix = cursor.0;
+                                            state.cursor_iy = cursor.1;
+                                            if selected { state.stop_input(); }
+                                            selected
+                                        },
+                                        _ => continue,
+                                    };
+                                    if selected { ui_tx.send(Msg::Enter).expect("UI failed"); }
+                                    cursor_moved = true;
+                                }
+                            },
+                            _ => continue,
+                        }
                     },
                     Msg::ArrowUp => {
+                        if state.ui_mode == UIView::PriceList {
+                            state.move_list_cursor(-1);
+                            cursor_moved = true;
+                        }
+                        if state.ui_mode == UIView::PriceTable {
+                            state.table_cursor.0 = state.table_cursor.0.saturating_sub(1);
+                            cursor_moved = true;
+                        }
+                        if state.ui_mode == UIView::Filter {
+                            state.filter_cursor = state.filter_cursor.saturating_sub(1);
+                        }
                         if state.ui_mode == UIView::Search {
                             if state.cursor_iy > 0 { 
                                 state.cursor_iy -= 1;
@@ -250,12 +829,31 @@ impl UI {
                         }
                     },
                     Msg::ArrowDown => {
+                        if state.ui_mode == UIView::PriceList {
+                            state.move_list_cursor(1);
+                            cursor_moved = true;
+                        }
+                        if state.ui_mode == UIView::PriceTable {
+                            state.table_cursor.0 += 1;  // clamped by `PriceTable`
+                            cursor_moved = true;
+                        }
+                        if state.ui_mode == UIView::Filter && state.filter_cursor + 1 < state.config.filters.len() {
+                            state.filter_cursor += 1;
+                        }
                         if state.ui_mode == UIView::Search {
                             state.cursor_iy += 1;   // ! height needs to be checked elsewhere!
                             cursor_moved = true;
                         }
                     },
                     Msg::ArrowLeft => {
+                        if state.ui_mode == UIView::PriceList {
+                            state.move_list_cursor(-(state.list_shown.0 as isize));
+                            cursor_moved = true;
+                        }
+                        if state.ui_mode == UIView::PriceTable {
+                            state.table_cursor.1 = state.table_cursor.1.saturating_sub(1);
+                            cursor_moved = true;
+                        }
                         if state.ui_mode == UIView::Search {
                             if state.cursor_ix > 0 { 
                                 state.cursor_ix -= 1;
@@ -264,26 +862,83 @@ impl UI {
                         }
                     },
                     Msg::ArrowRight => {
+                        if state.ui_mode == UIView::PriceList {
+                            state.move_list_cursor(state.list_shown.0 as isize);
+                            cursor_moved = true;
+                        }
+                        if state.ui_mode == UIView::PriceTable {
+                            state.table_cursor.1 += 1;  // clamped by `PriceTable`
+                            cursor_moved = true;
+                        }
                         if state.ui_mode == UIView::Search {
                             state.cursor_ix += 1;   // ! width needs to be checked elsewhere!
                             cursor_moved = true;
                         }
                     },
                     Msg::Home => {
+                        if state.ui_mode == UIView::PriceList {
+                            state.list_offset = 0;
+                        }
                         if state.ui_mode == UIView::Search {
                             state.cursor_ix = 0;
                             state.cursor_iy = 0;
                             cursor_moved = true;
                         }
                     },
-                    Msg::Enter => {
+                    Msg::PageUp => {
+                        if state.ui_mode == UIView::PriceList {
+                            state.list_offset = state.list_offset.saturating_sub(state.list_shown.1.max(1));
+                        }
                         if state.ui_mode == UIView::Search {
-                            state.message = format!("Graph {}", state.symbol);
+                            state.cursor_ix = state.cursor_ix.saturating_sub(state.search_scroll.1);
+                            cursor_moved = true;
+                        }
+                    },
+                    Msg::PageDown => {
+                        if state.ui_mode == UIView::PriceList {
+                            state.scroll_list(state.list_shown.1.max(1));
+                        }
+                        if state.ui_mode == UIView::Search {
+                            state.cursor_ix = state.cursor_ix.saturating_add(state.search_scroll.1);   // clamped by `Search`
+                            cursor_moved = true;
+                        }
+                    },
+                    Msg::Enter => {
+                        if state.ui_mode == UIView::Filter {
+                            if let Some(rule) = state.config.filters.get_mut(state.filter_cursor) {
+                                rule.enabled = !rule.enabled;
+                                state.message = format!("{} rule {}", if rule.enabled {"Enabled"} else {"Disabled"}, rule);
+                                state.filters_changed();
+                            }
+                        }
+                        if state.ui_mode == UIView::PriceTable {
+                            match state.table_symbol.clone() {
+                                Some(symbol) => { state.symbol = symbol; },
+                                None => {
+                                    state.message = String::from("No market for the selected cell");
+                                    UI::draw(&mut state, &mut terminal);
+                                    continue;
+                                }
+                            }
+                        }
+                        if [UIView::Search, UIView::PriceList, UIView::PriceTable, UIView::Heatmap].contains(&state.ui_mode) {
                             state.ui_mode_back = Some(state.ui_mode);
-                            state.ui_mode = UIView::Graph;
-                            UI::graph(&mut state, &mut terminal).await;
+                            UI::graph(&mut state, &ui_tx);
                         }
                     },
+                    Msg::Currency => {
+                        let next = match &state.config.currency {
+                            None => Some(0),
+                            Some(c) => CURRENCIES.iter().position(|x| x == c).map(|i| i + 1).filter(|i| *i < CURRENCIES.len()),
+                        };
+                        state.config.currency = next.map(|i| String::from(CURRENCIES[i]));
+                        state.message = match &state.config.currency {
+                            Some(c) => format!("Show prices in {}", c),
+                            None => String::from("Show prices in their quote currency"),
+                        };
+                        state.save_config(&["currency"]);
+                        state.ui_mode = UIView::PriceList;
+                    },
                     Msg::TogglePercent => {
                         state.show_percent = !state.show_percent;
                         if state.show_percent { state.message = String::from("Show %"); }
@@ -295,15 +950,20 @@ impl UI {
                         else { state.message = String::from("Show reduced"); }
                     },
                     Msg::Help => {
-                        state.ui_mode_back = Some(state.ui_mode);
-                        state.ui_mode = UIView::Help;
-                        state.message = String::from("Help");
+                        state.help = if state.help == Some(state.ui_mode) { None } else { Some(state.ui_mode) };
                     },
                     Msg::About => {
                         state.ui_mode_back = Some(state.ui_mode);
                         state.ui_mode = UIView::About;
                         state.message = String::from("About");
                     },
+                    Msg::Esc if state.help == Some(state.ui_mode) => {
+                        state.help = None;
+                    },
+                    Msg::Esc if state.loading.is_some() => {
+                        state.cancel_klines();
+                        state.message = String::from("Cancelled loading klines");
+                    },
                     Msg::Esc => {
                         state.ui_mode = state.ui_mode_back.unwrap_or(UIView::PriceList);
                         state.ui_mode_back = None;
@@ -315,6 +975,15 @@ impl UI {
                         return; 
                     }
                 }
+                // the help is closed when its view is left, so that it doesn't reopen when coming back
+                if state.help.is_some() && state.help != Some(state.ui_mode) { state.help = None; }
+                // leaving the search page, e.g. with M-r, ends typing
+                if state.input.is_some() && ![UIView::Search, UIView::Filter].contains(&state.ui_mode) { state.stop_input(); }
+                // the aggTrade stream of the trades page only runs while it is shown
+                if state.ui_mode != UIView::Trades {
+                    if let Some(task) = state.tape_task.take() { task.abort(); }
+                    state.tape = None;
+                }
                 UI::draw(&mut state, &mut terminal); 
                 if cursor_moved {
                     state.message = format!("SEL {}", state.symbol);
@@ -322,37 +991,60 @@ impl UI {
                 }
             }
         });
-        UI { tx: tx, handle: handle }
+        UI { tx, handle, input_mode }
     }
-    /// Draw Graph
-    pub async fn graph(mut state: &mut UIState, mut terminal: &mut Term) {
-        let interval: Interval = match state.time_scale {
-            1 => Interval::I5m,
-            2 => Interval::I15m,
-            3 => Interval::I30m,
-            4 => Interval::I1h,
-            5 => Interval::I2h,
-            6 => Interval::I4h,
-            7 => Interval::I8h,
-            8 => Interval::I12h,
-            9 => Interval::I1d,
-            _ => Interval::I1m,
-        };
-        state.message = format!("Getting {} klines for {}", interval.str(), state.symbol);
-        UI::draw(&mut state, &mut terminal);
-        match get_klines(&state.symbol, &interval).await {
-            Ok(klines) => {
-                state.ui_mode = UIView::Graph;
-                state.message = format!("Show {} klines for {}", interval.str(), state.symbol);
-                state.klines = Some(klines);
-            },
-            Err(e) => {
-                state.message = format!("Failed to get klines: {:?}", e);
+    /// Start downloading the klines of the selected symbol and time scale on a thread for blocking calls,
+    /// cancelling the previous download. The graph is shown when they arrive with `Msg::Klines`.
+    ///
+    /// A cancelled download stops before it touches the cache and sends nothing. Its request can't be
+    /// interrupted though, the thread ends when the request completes or times out after `HTTP_TIMEOUT`.
+    pub fn graph(state: &mut UIState, tx: &UnboundedSender<Msg>) {
+        state.cancel_klines();
+        let (symbol, time_scale) = (state.symbol.clone(), state.time_scale);
+        let interval = interval(time_scale);
+        state.loading = Some((symbol.clone(), time_scale, now_timestamp()));
+        state.message = format!("Getting {} klines for {}", interval.str(), symbol);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        state.klines_cancel = Some(cancelled.clone());
+        let (cache, tx) = (state.kline_cache.clone(), tx.clone());
+        tokio::task::spawn_blocking(move || {
+            if let Some(klines) = load_klines(&cache, &symbol, &interval, &cancelled) {
+                tx.send(Msg::Klines(symbol, time_scale, klines)).unwrap_or(());
+            }
+        });
+    }
+    /// Show trades of `state.symbol`, (re)starting the aggTrade stream if the symbol changed.
+    /// The stream is stopped when the page is left.
+    fn trades(state: &mut UIState, tx: &UnboundedSender<Msg>) {
+        if state.tape.as_ref().map(|tape| tape.symbol != state.symbol).unwrap_or(true) {
+            if let Some(task) = state.tape_task.take() { task.abort(); }
+            state.tape = Some(trades::TradeTape::new(state.symbol.clone()));
+            state.tape_task = Some(tokio::spawn(ws_agg_trades(vec![state.symbol.clone()], tx.clone(), Msg::TradeWS)));
+        }
+        state.ui_mode = UIView::Trades;
+        state.message = format!("Trades {}", state.symbol);
+    }
+    /// (Re)start the aggTrade stream of the symbols monitored for large trades:
+    /// the top symbols by volume plus those with their own threshold
+    fn watch_large_trades(state: &mut UIState, tx: &UnboundedSender<Msg>) {
+        if let Some(task) = state.large_task.take() { task.abort(); }
+        let config = &state.config.large_trades;
+        if config.threshold <= 0.0 && config.thresholds.is_empty() { return; }   // detector disabled
+        let mut symbols: Vec<Symbol> = state.infos.iter().flatten()
+                                            .take(config.symbols)
+                                            .map(|info| info.symbol.clone())
+                                            .collect();
+        if let Some(lookup) = &state.lookup {
+            for symbol in state.large.symbols() {
+                if lookup.contains_key(symbol) && !symbols.contains(symbol) { symbols.push(symbol.clone()); }
             }
         }
+        state.large_symbols = symbols.len();
+        state.large_task = Some(tokio::spawn(ws_agg_trades(symbols, tx.clone(), Msg::LargeTradeWS)));
     }
     /// Draw `UI`
     fn draw(state: &mut UIState, terminal: &mut Term) {
+        let ref_hits = Rc::new(RefCell::new(Vec::new()));  // widgets report their clickable cells
         terminal.draw(|f| {
             let size = f.size();
             let chunks = Layout::default()
@@ -367,21 +1059,50 @@ impl UI {
                 .split(size);
             match state.ui_mode {
                 UIView::PriceList => {
-                    if let Some(infos) = &state.infos {
-                        let price_list = price_list::PriceList::new(&infos, &state.markets, state.show_percent);
+                    if state.infos.is_some() {
+                        // rates of the quotes to the display currency, unconvertible quotes are missing
+                        let markets = &state.markets;
+                        let all_infos = state.all_infos.as_deref().unwrap_or(&[]);
+                        let mut rates: HashMap<Symbol, Decimal64> = HashMap::new();
+                        if let Some(currency) = &state.config.currency {
+                            for info in state.list_infos.iter() {
+                                if rates.contains_key(&info.quote) { continue; }
+                                if let Some(rate) = cross_rate(&info.quote, currency, all_infos, |s| markets.get(s).map(|m| m.px)) {
+                                    rates.insert(info.quote.clone(), rate);
+                                }
+                            }
+                        }
+                        let conversion = state.config.currency.as_deref().map(|currency| (currency, &rates));
+                        let history = if state.config.sparklines { Some(&state.history) } else { None };
+                        let ref_shown = Rc::new(RefCell::new(state.list_shown));
+                        let options = price_list::ListOptions { show_percent: state.show_percent, conversion, history };
+                        let refs = price_list::ListRefs { shown: ref_shown.clone(), hits: ref_hits.clone() };
+                        let price_list = price_list::PriceList::new(&state.list_infos, &state.markets, options,
+                                                                    state.list_offset, &state.symbol, refs);
                         f.render_widget(price_list, chunks[0]);
+                        state.list_shown = (*ref_shown).take();
                     }
                 },
                 UIView::PriceTable => {
                     if let Some(infos) = &state.infos {
-                        let price_table = price_table::PriceTable::new(&infos, &state.markets, state.show_percent, state.extended);
+                        // `PriceTable` clamps the cursor to the visible cells and reports the selected symbol
+                        let ref_cursor = Rc::new(RefCell::new(state.table_cursor));
+                        let ref_symbol = Rc::new(RefCell::new(None));
+                        let options = price_table::TableOptions { show_percent: state.show_percent, extended: state.extended,
+                                                                  columns: &state.config.table_quotes };
+                        let refs = price_table::TableRefs { cursor: ref_cursor.clone(), symbol: ref_symbol.clone(),
+                                                            hits: ref_hits.clone() };
+                        let price_table = price_table::PriceTable::new(infos, &state.markets, options, refs);
                         f.render_widget(price_table, chunks[0]);
+                        state.table_cursor = (*ref_cursor).take();
+                        state.table_symbol = (*ref_symbol).take();
+                        if let Some(symbol) = &state.table_symbol { state.symbol = symbol.clone(); }
                     }
                 },
                 UIView::Graph => {
                     if let Some(infos) = &mut state.infos {
                         if let Some(klines) = &state.klines {
-                            let graph = graph::Graph::new(&infos, klines, Interval::I1m, state.symbol.clone());
+                            let graph = graph::Graph::new(infos, klines, Interval::I1m, state.symbol.clone(), state.crosshair);
                             f.render_widget(graph, chunks[0]);
                         }
                     }
@@ -392,48 +1113,115 @@ impl UI {
                     // - i_symbol is the index of the selected symbol
                     // - cursor is unchanged unless the display bounds are be exceeded
                     if let Some(infos) = &state.infos {
+                        let query = state.input.as_deref().unwrap_or("");
+                        let matches = fuzzy::rank(query, infos, &state.config.aliases);
                         let ref_i_symbol = Rc::new(RefCell::new(0));
                         let ref_cursor = Rc::new(RefCell::new((state.cursor_ix,state.cursor_iy)));
-                        let search = search::Search::new(infos, ref_i_symbol.clone(), ref_cursor.clone());
+                        let ref_scroll = Rc::new(RefCell::new(state.search_scroll));
+                        let search = search::Search::new(matches.clone(), query, ref_i_symbol.clone(), 
+                                                         ref_cursor.clone(), ref_scroll.clone(), ref_hits.clone());
                         f.render_widget(search, chunks[0]);
+                        // Finally adjust state.symbol if necessary
+                        let i_symbol: usize = (*ref_i_symbol).take();
+                        let symbol = matches.get(i_symbol).map(|info| info.symbol.clone()); // check bounds just in case
                         // Now stick cursor (ix, iy) back into state
                         let (ix, iy) = (*ref_cursor).take();
                         state.cursor_ix = ix; state.cursor_iy = iy;
-                        // Finally adjust state.symbol if necessary
-                        if let Some(infos) = &state.infos {
-                            let i_symbol: usize = (*ref_i_symbol).take();
-                            if i_symbol < infos.len() { // check bounds just in case
-                                let symbol = &infos[i_symbol].symbol;
-                                state.symbol = symbol.clone();
-                            }
-                        }
+                        state.search_scroll = (*ref_scroll).take();
+                        if let Some(symbol) = symbol { state.symbol = symbol; }
+                    }
+                },
+                UIView::Trades => {
+                    if let Some(tape) = &state.tape {
+                        let info = state.lookup.as_ref().and_then(|lookup| lookup.get(&tape.symbol));
+                        f.render_widget(trades::Trades::new(tape, info), chunks[0]);
                     }
                 },
+                UIView::LargeTrades => {
+                    let large_trades = large_trades::LargeTrades::new(&state.large, state.large_symbols);
+                    f.render_widget(large_trades, chunks[0]);
+                },
+                UIView::Detail => {
+                    let info = state.lookup.as_ref().and_then(|lookup| lookup.get(&state.symbol));
+                    let detail = detail::Detail::new(&state.symbol, info, state.markets.get(&state.symbol), &state.history);
+                    f.render_widget(detail, chunks[0]);
+                },
+                UIView::Portfolio => {
+                    let markets = &state.markets;
+                    let infos = state.all_infos.as_deref().unwrap_or(&[]);
+                    let positions = state.portfolio.valuate(infos, |s| markets.get(s).map(|m| m.px),
+                                                            |s| markets.get(s).map(|m| m.px_24h));
+                    f.render_widget(positions::Positions::new(&positions), chunks[0]);
+                },
+                UIView::Heatmap => {
+                    if let Some(infos) = &state.infos {
+                        let heatmap = heatmap::Heatmap::new(infos, &state.markets, &state.symbol, ref_hits.clone());
+                        f.render_widget(heatmap, chunks[0]);
+                    }
+                },
+                UIView::Movers => {
+                    if let Some(infos) = &state.infos {
+                        let config = &state.config.movers;
+                        let movers = movers::Movers::new(infos, &state.markets, config.count, config.min_volume);
+                        f.render_widget(movers, chunks[0]);
+                    }
+                },
+                UIView::Arbitrage => {
+                    // best bid and ask of all markets, cycles with a missing price are left out
+                    let quotes: HashMap<&Symbol, (f64, f64)> = state.markets.iter()
+                        .map(|(symbol, mkt)| (symbol, (dec_to_f64(mkt.bid), dec_to_f64(mkt.ask)))).collect();
+                    let quote = |s: &Symbol| quotes.get(s).cloned();
+                    let fee = state.config.arbitrage.fee;
+                    let mut ranked: Vec<(f64, &Cycle)> = state.cycles.iter()
+                        .filter_map(|cycle| Some((cycle.rate(&quote, fee / 100.0)?, cycle))).collect();
+                    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+                    f.render_widget(cycles::Cycles::new(&ranked, state.cycles.len(), fee), chunks[0]);
+                },
+                UIView::Filter => {
+                    let n_shown = state.infos.as_ref().map(|infos| infos.len()).unwrap_or(0);
+                    let n_total = state.all_infos.as_ref().map(|infos| infos.len()).unwrap_or(0);
+                    let filters = filters::Filters::new(&state.config.filters, state.filter_cursor,
+                                                        state.input.as_deref(), n_shown, n_total);
+                    f.render_widget(filters, chunks[0]);
+                },
                 UIView::Empty => {
                     // draw splash screen
                     about::draw_about(f, chunks[0]);
                 }
-                UIView::Help => {
-                    f.render_widget(help::help(), chunks[0]);
-                },
                 UIView::About => {
                     about::draw_about(f, chunks[0]);
                 }
             }
+            if state.help == Some(state.ui_mode) {
+                help::draw_help(f, chunks[0], &state.keys, state.ui_mode, state.input.is_some());
+            }
             UI::draw_message_bar(f, state, chunks[1]);
         }).expect("Failed to draw!");
+        state.hits = ref_hits.take();
     }
     /// Draw the message bar at the bottom
     fn draw_message_bar<B: Backend>(f: &mut Frame<B>, state: &UIState, area: Rect) {
-        // layout horizontally into three pieces:
+        // layout horizontally into four pieces:
         // - current time
         // - state.message
+        // - visible symbols and active sort (price list only)
         // - latency (floating right)
+        let sort = if state.ui_mode == UIView::PriceList {
+            let n = state.list_infos.len();
+            let first = (state.list_offset + 1).min(n);
+            let last = (state.list_offset + state.list_shown.1).min(n);
+            let currency = state.config.currency.as_ref().map(|c| format!("in {} | ", c)).unwrap_or_default();
+            format!(" symbols {}–{} of {} | {}sort: {} {} ", first, last, n, currency,
+                    state.sort_key.str(), if state.sort_desc {"↓"} else {"↑"})
+        } else {
+            String::new()
+        };
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(
                 [ Constraint::Length(13)    // 13 chars in "| HH:MM:SS | "
                 , Constraint::Min(0)
+                , Constraint::Length(sort.chars().count() as u16)
                 , Constraint::Length(9)     // enough for 99999ms
                 ].as_ref()
             )
@@ -444,21 +1232,34 @@ impl UI {
             Span::styled(format!("{}", now.format("%H:%M:%S")), Style::default().add_modifier(Modifier::ITALIC)),
             Span::from(" | ")
         ]);
-        let msg_span = Span::from(state.message.as_str());
+        let msg_span = match &state.loading {
+            Some((_, _, ts)) => {
+                // loading indicator, spinning while the screen is redrawn
+                let elapsed = now_timestamp().saturating_sub(*ts);
+                let spinner = SPINNER[(elapsed / 100) as usize % SPINNER.len()];
+                Spans::from(vec![
+                    Span::styled(format!("{} ", spinner), Style::default().fg(theme::current().accent)),
+                    Span::raw(format!("{} {:.1}s", state.message, elapsed as f64 / 1000.0)),
+                ])
+            },
+            None => Spans::from(state.message.as_str()),
+        };
         let lat_span = if state.ts_last_update != 0 {
             let delta = now.timestamp_millis() as u64-state.ts_last_update;
             let s = format!("{}ms", delta);
+            let theme = theme::current();
             let style = Style::default().fg(
-                if      delta < 5000  { Color::Green  }
-                else if delta < 15000 { Color::Yellow }
-                else                  { Color::Red    }
+                if      delta < 5000  { theme.up   }
+                else if delta < 15000 { theme.warn }
+                else                  { theme.down }
             );
             Span::styled(s, style)
         } else {
-            Span::styled("- ms", Style::default().fg(Color::Gray))
+            Span::styled("- ms", theme::current().muted_style())
         };
         f.render_widget(Paragraph::new(now_span), chunks[0]);
         f.render_widget(Paragraph::new(msg_span), chunks[1]);
-        f.render_widget(Paragraph::new(lat_span).alignment(Alignment::Right), chunks[2]);
+        f.render_widget(Paragraph::new(Span::styled(sort, Style::default().add_modifier(Modifier::ITALIC))), chunks[2]);
+        f.render_widget(Paragraph::new(lat_span).alignment(Alignment::Right), chunks[3]);
     }
 }
\ No newline at end of file
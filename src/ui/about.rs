@@ -1,6 +1,6 @@
 /// The about page
 use tui::{
-    style::{Style, Color, Modifier},
+    style::{Style, Modifier},
     widgets::{Paragraph},
     layout::{Alignment, Rect, Layout, Direction, Constraint},
     text::{Span, Spans},
@@ -8,32 +8,34 @@ use tui::{
     terminal::Frame,
 };
 use version::version;
+use crate::theme;
 
 /// The about paragraph
 fn about<'a>() -> (Paragraph<'a>, u16) {
+    let theme = theme::current();
     let txt = vec![
-        Spans::from(Span::styled("     ####    #####    ######  ##   ##  ####      ######  ##   ##  #######  ", Style::default().fg(Color::LightCyan))),
-        Spans::from(Span::styled("    ##  ##  ### ###     ##    ###  ##   ##         ##    ##   ##   ##   #  ", Style::default().fg(Color::LightCyan))),
-        Spans::from(Span::styled("   ##       ##   ##     ##    #### ##   ##         ##    ##   ##   ##      ", Style::default().fg(Color::LightCyan))),
-        Spans::from(Span::styled("   ##       ##   ##     ##    #######   ##         ##     ## ##    ####    ", Style::default().fg(Color::LightCyan))),
-        Spans::from(Span::styled("   ##       ##   ##     ##    ## ####   ##         ##     ## ##    ##      ", Style::default().fg(Color::LightCyan))),
-        Spans::from(Span::styled("    ##  ##  ### ###     ##    ##  ###   ##  ##     ##      ###     ##   #  ", Style::default().fg(Color::LightCyan))),
-        Spans::from(Span::styled("     ####    #####    ######  ##   ##  #######   ######    ###    #######  ", Style::default().fg(Color::LightCyan))),
+        Spans::from(Span::styled("     ####    #####    ######  ##   ##  ####      ######  ##   ##  #######  ", Style::default().fg(theme.accent))),
+        Spans::from(Span::styled("    ##  ##  ### ###     ##    ###  ##   ##         ##    ##   ##   ##   #  ", Style::default().fg(theme.accent))),
+        Spans::from(Span::styled("   ##       ##   ##     ##    #### ##   ##         ##    ##   ##   ##      ", Style::default().fg(theme.accent))),
+        Spans::from(Span::styled("   ##       ##   ##     ##    #######   ##         ##     ## ##    ####    ", Style::default().fg(theme.accent))),
+        Spans::from(Span::styled("   ##       ##   ##     ##    ## ####   ##         ##     ## ##    ##      ", Style::default().fg(theme.accent))),
+        Spans::from(Span::styled("    ##  ##  ### ###     ##    ##  ###   ##  ##     ##      ###     ##   #  ", Style::default().fg(theme.accent))),
+        Spans::from(Span::styled("     ####    #####    ######  ##   ##  #######   ######    ###    #######  ", Style::default().fg(theme.accent))),
         Spans::from(Vec::new()),
         Spans::from(Vec::new()),
         Spans::from(Span::styled("Live cryptocurrency prices CLI",
-                                 Style::default().fg(Color::LightCyan).add_modifier(Modifier::ITALIC))),
+                                 Style::default().fg(theme.accent).add_modifier(Modifier::ITALIC))),
         Spans::from(Vec::new()),
         Spans::from(Vec::new()),
-        Spans::from(Span::styled("(c) Mayer Analytics, GPL-3.0", Style::default().fg(Color::Red))),
+        Spans::from(Span::styled("(c) Mayer Analytics, GPL-3.0", Style::default().fg(theme.down))),
         Spans::from(Vec::new()),
-        Spans::from(Span::styled("https://github.com/mayeranalytics/coinlive", Style::default().fg(Color::Green))),
+        Spans::from(Span::styled("https://github.com/mayeranalytics/coinlive", Style::default().fg(theme.up))),
         Spans::from(Vec::new()),
-        Spans::from(Span::styled(format!("Version {}", version!()), Style::default().fg(Color::Gray))),
+        Spans::from(Span::styled(format!("Version {}", version!()), theme.muted_style())),
     ];
     let h = txt.len() as u16;
     let p = Paragraph::new(txt)
-        .style(Style::default().fg(Color::LightCyan))
+        .style(Style::default().fg(theme.accent))
         .alignment(Alignment::Center);
     (p, h)
 }
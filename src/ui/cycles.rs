@@ -0,0 +1,60 @@
+//! Widget `Cycles` with the best and worst triangular arbitrage cycles
+use crate::arbitrage::Cycle;
+use crate::theme;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::{Rect},
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+
+/// Widget Cycles
+///
+/// The cycles with the highest return on the left, those with the lowest on the right.
+pub struct Cycles<'a> {
+    ranked: &'a [(f64, &'a Cycle)],     // cycles with a price and their return, highest first
+    n_cycles: usize,                    // number of cycles, including those without prices
+    fee: f64,                           // fee per trade in %
+}
+
+impl<'a> Cycles<'a> {
+    pub fn new(ranked: &'a [(f64, &'a Cycle)], n_cycles: usize, fee: f64) -> Cycles<'a> {
+        Cycles { ranked, n_cycles, fee }
+    }
+    /// Draw `cycles` with a title at `x`, `width` wide
+    fn render_column<'b, I>(&self, title: &str, cycles: I, x: u16, width: u16, area: Rect, buf: &mut Buffer)
+        where I: Iterator<Item=&'b (f64, &'b Cycle)>, 'a: 'b {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let columns = Spans::from(vec![
+            Span::styled(format!("{:<30} {:>9}  {}", title, "Return", "Markets"), bold)
+        ]);
+        buf.set_spans(x, area.y + 1, &columns, width);
+        for (y, (rate, cycle)) in cycles.take(area.height as usize - 2).enumerate() {
+            let ret = 100.0 * (rate - 1.0);
+            let style = if ret > 0.0 { theme::current().up_style() } else { theme::current().down_style() };
+            let markets: Vec<&str> = cycle.legs.iter().map(|leg| &*leg.symbol).collect();
+            let spans = Spans::from(vec![
+                Span::styled(format!("{:<30} ", cycle.path()), bold),
+                Span::styled(format!("{:>+8.3}%  ", ret), style),
+                Span::styled(markets.join(" "), theme::current().muted_style()),
+            ]);
+            buf.set_spans(x, area.y + y as u16 + 2, &spans, width);
+        }
+    }
+}
+
+impl<'a> Widget for Cycles<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 { return; }
+        let header = Spans::from(vec![
+            Span::styled("Triangular arbitrage ", Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC)),
+            Span::raw(format!(" {} of {} cycles priced, {}% fee per trade, return after fees at bid/ask",
+                              self.ranked.len(), self.n_cycles, self.fee)),
+        ]);
+        buf.set_spans(area.x, area.y, &header, area.width);
+        let width = area.width / 2;
+        self.render_column("Best", self.ranked.iter(), area.x, width.saturating_sub(2), area, buf);
+        self.render_column("Worst", self.ranked.iter().rev(), area.x + width, width, area, buf);
+    }
+}
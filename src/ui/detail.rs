@@ -0,0 +1,131 @@
+//! Widget `Detail` with the full 24h statistics of a single symbol
+use crate::utils::*;
+use crate::ui::MarketState;
+use crate::theme;
+use crate::history::TickHistory;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::{Rect},
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+use dec::Decimal64;
+use chrono::{Local, TimeZone};
+
+/// Widget Detail
+///
+/// Shows last price, 24h change, open/high/low, volumes, number of trades, best bid/ask
+/// and a bar indicating where the last price sits in the 24h range, followed by the range of the
+/// prices recorded since start-up.
+pub struct Detail<'a> {
+    symbol: &'a Symbol,
+    info: Option<&'a Info>,
+    mkt: Option<&'a MarketState>,
+    history: &'a TickHistory,
+}
+
+impl<'a> Detail<'a> {
+    pub fn new(symbol: &'a Symbol, info: Option<&'a Info>, mkt: Option<&'a MarketState>, history: &'a TickHistory) -> Detail<'a> {
+        Detail { symbol, info, mkt, history }
+    }
+    /// The lines label: value
+    fn rows(&self, mkt: &MarketState) -> Vec<(String, Span<'a>)> {
+        let (base, quote) = match self.info {
+            Some(info) => (info.base.to_string(), info.quote.to_string()),
+            None => (String::from("base"), String::from("quote")),
+        };
+        let hundred = Decimal64::from(100);
+        let change = mkt.px - mkt.open;
+        let change_pct = change / mkt.open * hundred;
+        let spread = mkt.ask - mkt.bid;
+        let spread_bps = spread / ((mkt.ask + mkt.bid) / Decimal64::from(2)) * Decimal64::from(10000);
+        let ticks = self.history.ticks(self.symbol);
+        let recorded = match ticks.clone().next() {
+            Some(first) => {
+                let t = Local.timestamp_millis_opt(first.ts as i64).single().map(|t| t.format("%H:%M:%S").to_string());
+                let low = ticks.clone().map(|tick| tick.px).fold(f64::INFINITY, f64::min);
+                let high = ticks.clone().map(|tick| tick.px).fold(f64::NEG_INFINITY, f64::max);
+                format!("{} since {}, {} - {}", ticks.len(), t.unwrap_or_default(), fmt_dec(f64_to_dec(low)), fmt_dec(f64_to_dec(high)))
+            },
+            None => String::from("-"),
+        };
+        let mut last_5m = self.history.since(self.symbol, now_timestamp().saturating_sub(5 * 60 * 1000));
+        let change_5m = match (last_5m.next(), last_5m.last()) {
+            (Some(first), Some(last)) => Span::styled(format!("{:+.2}%", 100.0 * (last.px / first.px - 1.0)),
+                                                      if last.px >= first.px { theme::current().up_style() } else { theme::current().down_style() }),
+            _ => Span::raw("-"),
+        };
+        let memory = format!("{} ticks of all symbols, {:.1} MB", self.history.n_ticks(), self.history.memory() as f64 / 1e6);
+        vec![
+            (String::from("Last price"), Span::styled(fmt_dec(mkt.px), mkt.style())),
+            (String::from("24h change"), Span::styled(format!("{} ({:+.2}%)", fmt_dec(change), dec_to_f64(change_pct)),
+                                                      mkt.style_percent())),
+            (String::from("24h open"), Span::raw(fmt_dec(mkt.open))),
+            (String::from("24h high"), Span::raw(fmt_dec(mkt.high))),
+            (String::from("24h low"), Span::raw(fmt_dec(mkt.low))),
+            (format!("24h volume {}", base), Span::raw(fmt_dec(mkt.volume))),
+            (format!("24h volume {}", quote), Span::raw(fmt_dec(mkt.quote_volume))),
+            (String::from("24h trades"), Span::raw(format!("{}", mkt.n_trades))),
+            (String::from("Best bid"), Span::styled(fmt_dec(mkt.bid), theme::current().up_style())),
+            (String::from("Best ask"), Span::styled(fmt_dec(mkt.ask), theme::current().down_style())),
+            (String::from("Spread"), Span::raw(format!("{} ({:.1} bps)", fmt_dec(spread), dec_to_f64(spread_bps)))),
+            (String::from("Recorded ticks"), Span::raw(recorded)),
+            (String::from("Recorded 5min change"), change_5m),
+            (String::from("Tick history"), Span::styled(memory, theme::current().muted_style())),
+        ]
+    }
+    /// The 24h range bar `low ├───●──┤ high`
+    fn range_bar(&self, mkt: &MarketState, width: usize) -> Spans<'a> {
+        let low = fmt_dec(mkt.low);
+        let high = fmt_dec(mkt.high);
+        let inner = width.saturating_sub(low.len() + high.len() + 4).max(1);  // cells between ├ and ┤
+        let f = dec_to_f64((mkt.px - mkt.low) / (mkt.high - mkt.low));
+        let (left, marker, right) = if f.is_finite() {
+            let pos = (f.clamp(0.0, 1.0) * (inner-1) as f64).round() as usize;
+            ("─".repeat(pos), "●", "─".repeat(inner-1-pos))
+        } else {
+            ("─".repeat(inner), "", String::new())
+        };
+        Spans::from(vec![
+            Span::raw(format!("{} ├", low)),
+            Span::raw(left),
+            Span::styled(marker, mkt.style_percent().add_modifier(Modifier::BOLD)),
+            Span::raw(right),
+            Span::raw(format!("┤ {}", high)),
+        ])
+    }
+}
+
+impl<'a> Widget for Detail<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let mut title = vec![Span::styled(format!("{} ", self.symbol), bold.add_modifier(Modifier::ITALIC))];
+        if let Some(info) = self.info {
+            title.push(Span::raw(format!(" {} / {}", info.base, info.quote)));
+        }
+        buf.set_spans(area.x, area.y, &Spans::from(title), area.width);
+        let mkt = match self.mkt {
+            Some(mkt) => mkt,
+            None => {
+                let spans = Spans::from(Span::styled("No market data yet", theme::current().muted_style()));
+                buf.set_spans(area.x, area.y + 2, &spans, area.width);
+                return;
+            }
+        };
+        let rows = self.rows(mkt);
+        let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        let mut y = area.y + 2;
+        for (label, value) in rows.into_iter() {
+            if y >= area.y + area.height { return; }
+            let spans = Spans::from(vec![Span::styled(format!("{:<width$}  ", label, width=label_width), bold), value]);
+            buf.set_spans(area.x, y, &spans, area.width);
+            y += 1;
+        }
+        y += 1;
+        if y + 1 >= area.y + area.height { return; }
+        buf.set_spans(area.x, y, &Spans::from(Span::styled("24h range", bold)), area.width);
+        let bar = self.range_bar(mkt, (area.width as usize).min(80));
+        buf.set_spans(area.x, y + 1, &bar, area.width);
+    }
+}
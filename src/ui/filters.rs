@@ -0,0 +1,74 @@
+//! Widget `Filters` for viewing and editing the symbol filter rules
+use crate::filter::Rule;
+use crate::theme;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::{Rect},
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+
+/// Widget Filters
+///
+/// Lists the filter rules with a cursor, the number of symbols shown and, while a new rule
+/// is typed, the input line.
+pub struct Filters<'a> {
+    rules: &'a [Rule],
+    cursor: usize,              // index of the selected rule
+    input: Option<&'a str>,     // rule being typed
+    n_shown: usize,             // number of symbols passing the filter
+    n_total: usize,             // number of symbols
+}
+
+impl<'a> Filters<'a> {
+    pub fn new(rules: &'a [Rule], cursor: usize, input: Option<&'a str>, n_shown: usize, n_total: usize) -> Filters<'a> {
+        Filters { rules, cursor, input, n_shown, n_total }
+    }
+}
+
+impl<'a> Widget for Filters<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let grey = theme::current().muted_style();
+        let mut lines: Vec<Spans> = vec![
+            Spans::from(vec![
+                Span::styled("Filter rules ", bold.add_modifier(Modifier::ITALIC)),
+                Span::raw(format!(" showing {} of {} symbols", self.n_shown, self.n_total)),
+            ]),
+            Spans::default(),
+        ];
+        if self.rules.is_empty() {
+            lines.push(Spans::from(Span::styled("  no rules, all symbols are shown", grey)));
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            let mut style = if rule.enabled { Style::default() } else { grey };
+            if i == self.cursor && self.input.is_none() { style = style.add_modifier(Modifier::REVERSED); }
+            let check = if rule.enabled { "[x]" } else { "[ ]" };
+            lines.push(Spans::from(Span::styled(format!("  {} {}", check, rule), style)));
+        }
+        lines.push(Spans::default());
+        if let Some(input) = self.input {
+            lines.push(Spans::from(vec![
+                Span::styled("New rule: ", bold),
+                Span::raw(String::from(input)),
+                Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+            ]));
+            lines.push(Spans::from(Span::styled("Enter to add, Esc to cancel", grey)));
+        } else {
+            lines.push(Spans::from(Span::styled("+ add rule, - delete rule, Enter enable/disable rule, Esc back", grey)));
+        }
+        lines.push(Spans::default());
+        for help in [
+            "Syntax: <field> <op> <value>",
+            "  symbol, base, quote, status: = or != with glob patterns using * and ?",
+            "  volume: >= in USDT",
+            "Examples: quote != BUSD, symbol != *UPUSDT, symbol != *DOWNUSDT, volume >= 1000000",
+        ].iter() {
+            lines.push(Spans::from(Span::styled(*help, grey)));
+        }
+        for (y, spans) in lines.iter().take(area.height as usize).enumerate() {
+            buf.set_spans(area.x, area.y + y as u16, spans, area.width);
+        }
+    }
+}
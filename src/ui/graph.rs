@@ -1,8 +1,9 @@
 ///! Widget `Graph`
 use crate::utils::*;
 use crate::ui::nice::{f64_nice_range, Nice};
+use crate::theme;
 use tui::{
-    style::{Style, Color, Modifier},
+    style::{Style, Modifier},
     widgets::{Axis, Chart, Widget, Block, Dataset, GraphType, Paragraph},
     layout::{Rect},
     text::{Span},
@@ -11,33 +12,49 @@ use tui::{
 };
 use chrono::{Utc, prelude::DateTime};
 use std::time::{UNIX_EPOCH, Duration};
-use inlinable_string::InlineString;
 
 
 /// Widget Graph
 /// 
-/// Shows a time/closing-price graph of a symbol.
+/// Shows a time/closing-price graph of a symbol. The optional crosshair marks the bar closest
+/// to a screen column, its time and OHLC values are shown in the title.
 pub struct Graph<'a> {
     symbol: Symbol,
     infos: &'a Vec<Info>,   // sorted list of `Info`
     klines: &'a Vec<Bar>,
     interval: Interval,     // 1m, 3m, 5m, etc.
+    crosshair: Option<u16>, // screen column of the crosshair
 }
 
 impl<'a> Graph<'a> {
-    pub fn new(infos: &'a Vec<Info>, klines: &'a Vec<Bar>, interval: Interval, symbol: Symbol) -> Graph<'a> {
-        Graph { symbol: symbol, infos: infos, klines: klines, interval: interval }
+    pub fn new(infos: &'a Vec<Info>, klines: &'a Vec<Bar>, interval: Interval, symbol: Symbol,
+               crosshair: Option<u16>) -> Graph<'a> {
+        Graph { symbol, infos, klines, interval, crosshair }
+    }
+    /// The bar closest to screen column `x`, given the plot spans columns `left..right` and times `t_min..t_max`
+    fn bar_at(&self, x: u16, left: u16, right: u16, t_min: f64, t_max: f64) -> &'a Bar {
+        let f = if right > left + 1 { (x.clamp(left, right - 1) - left) as f64 / (right - left - 1) as f64 } else { 0.0 };
+        let t = t_min + f * (t_max - t_min);
+        let klines: &'a Vec<Bar> = self.klines;
+        let ix = klines.partition_point(|bar| (bar.t as f64) < t);
+        match (ix.checked_sub(1).and_then(|i| klines.get(i)), klines.get(ix)) {
+            (Some(a), Some(b)) if t - a.t as f64 <= b.t as f64 - t => a,
+            (_, Some(b)) => b,
+            (Some(a), None) => a,
+            (None, None) => &klines[0],   // klines is not empty
+        }
     }
 }
 
 impl<'a> Widget for Graph<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        let theme = theme::current();
         if self.klines.len() < 1 {
             Paragraph::new("No data!")
-            .style(Style::default().fg(Color::Red))
+            .style(Style::default().fg(theme.down))
             .block(
                 Block::default()
-                    .style(Style::default().fg(Color::White))
+                    .style(Style::default().fg(theme.text))
                     .title("Error")
             ).render(area, buf);
             return;
@@ -71,13 +88,51 @@ impl<'a> Widget for Graph<'a> {
 
         }
         let (p_min, p_max) = f64_nice_range(p_min, p_max);
+        let y_labels = vec![
+            Span::styled(
+                p_min.compact_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(((p_min+p_max)/2.0).compact_str()),
+            Span::styled(
+                p_max.compact_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ];
+        // crosshair: vertical and horizontal line through the close of the selected bar
+        let mut title = String::from(&*self.symbol);
+        let mut vertical: Vec<(f64,f64)> = Vec::new();
+        let mut horizontal: Vec<(f64,f64)> = Vec::new();
+        if let Some(x) = self.crosshair {
+            // the plot starts right of the y labels and the y axis
+            let left = area.x + y_labels.iter().map(|l| l.width()).max().unwrap_or(0) as u16 + 1;
+            let bar = self.bar_at(x, left, area.x + area.width, t_min, t_max);
+            let t = bar.t as f64 + delta/2.0;
+            vertical = vec![(t, p_min), (t, p_max)];
+            horizontal = vec![(t_min, bar.c as f64), (t_max, bar.c as f64)];
+            let ts = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_millis(bar.t));
+            title = format!("{}  {}  o {} h {} l {} c {}", title, ts.format("%Y-%m-%d %H:%M"),
+                            (bar.o as f64).compact_str(), (bar.h as f64).compact_str(),
+                            (bar.l as f64).compact_str(), (bar.c as f64).compact_str());
+        }
+        let crosshair_style = Style::default().fg(theme.crosshair);
         let datasets = vec![
             Dataset::default()
                 //.name(self.symbol.unwrap_or(&default_name))
                 .marker(symbols::Marker::Braille)
                 .graph_type(GraphType::Line)
-                .style(Style::default().fg(Color::Cyan))
-                .data(data.as_slice())
+                .style(Style::default().fg(theme.accent))
+                .data(data.as_slice()),
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(crosshair_style)
+                .data(vertical.as_slice()),
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(crosshair_style)
+                .data(horizontal.as_slice()),
         ];
         let t1 = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_millis(t_min as u64));
         let t2 = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_millis(((t_min+t_max)/2.0) as u64));
@@ -93,27 +148,15 @@ impl<'a> Widget for Graph<'a> {
                 Style::default().add_modifier(Modifier::BOLD),
             ),
         ];
-        let y_labels = vec![
-            Span::styled(
-                p_min.compact_str(),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::raw(((p_min+p_max)/2.0).compact_str()),
-            Span::styled(
-                p_max.compact_str(),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-        ];
-        let title: InlineString = self.symbol;
         let graph = Chart::new(datasets)
-            .block(Block::default().title(String::from(&*title)))
+            .block(Block::default().title(title))
             .x_axis(Axis::default()
-                .style(Style::default().fg(Color::White))
+                .style(Style::default().fg(theme.text))
                 .bounds([t_min, t_max])
                 .labels(x_labels))
             .y_axis(Axis::default()
                 //.title(Span::styled("", Style::default().fg(Color::Red)))
-                .style(Style::default().fg(Color::White))
+                .style(Style::default().fg(theme.text))
                 .bounds([p_min, p_max])
                 .labels(y_labels));
         graph.render(area, buf);
@@ -0,0 +1,151 @@
+//! Widget `Heatmap`, a treemap of the market
+use crate::utils::*;
+use crate::ui::{MarketState, Hits};
+use crate::theme;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::Rect,
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Number of symbols (by volume) in the heatmap, the smallest usually get no tile
+const HEATMAP_SYMBOLS: usize = 100;
+/// 24h change in % with full colour intensity
+const FULL_CHANGE: f64 = 10.0;
+
+/// Widget Heatmap
+///
+/// Each symbol is a tile whose area is proportional to its volume in USDT, coloured by its 24h change.
+pub struct Heatmap<'a> {
+    infos: &'a [Info],                          // sorted list of `Info`
+    markets: &'a HashMap<Symbol, MarketState>,  // map symbol to `MarketState`
+    selected: &'a Symbol,                       // highlighted symbol
+    pub ref_hits: Rc<RefCell<Hits>>,            // tiles and their index in `infos` (interior mutability via Rc<RefCell<_>>)
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(infos: &'a [Info], markets: &'a HashMap<Symbol, MarketState>, selected: &'a Symbol,
+               ref_hits: Rc<RefCell<Hits>>) -> Heatmap<'a> {
+        Heatmap { infos, markets, selected, ref_hits }
+    }
+}
+
+/// Worst aspect ratio of the tiles with `areas` in a row along a side of length `side`
+fn worst(areas: &[f64], side: f64) -> f64 {
+    let sum: f64 = areas.iter().sum();
+    let max = areas.iter().cloned().fold(f64::MIN, f64::max);
+    let min = areas.iter().cloned().fold(f64::MAX, f64::min);
+    (side * side * max / (sum * sum)).max(sum * sum / (side * side * min))
+}
+
+/// Squarified treemap: split `width` x `height` into rectangles `(x, y, w, h)` with areas proportional to
+/// `weights`, which must be sorted in descending order, keeping the rectangles as square as possible
+pub fn treemap(weights: &[f64], width: f64, height: f64) -> Vec<(f64, f64, f64, f64)> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 { return Vec::new(); }
+    let areas: Vec<f64> = weights.iter().map(|w| w / total * width * height).collect();
+    let mut out = Vec::with_capacity(areas.len());
+    let (mut x, mut y, mut w, mut h) = (0.0, 0.0, width, height);
+    let mut i = 0;
+    while i < areas.len() {
+        // add tiles to the row along the shorter side while that makes them squarer
+        let side = w.min(h);
+        let mut j = i + 1;
+        while j < areas.len() && worst(&areas[i..j+1], side) <= worst(&areas[i..j], side) { j += 1; }
+        let sum: f64 = areas[i..j].iter().sum();
+        if w >= h {
+            // row is a column at the left
+            let cw = sum / h;
+            let mut ty = y;
+            for a in areas[i..j].iter() {
+                out.push((x, ty, cw, a / cw));
+                ty += a / cw;
+            }
+            x += cw;
+            w -= cw;
+        } else {
+            // row at the top
+            let rh = sum / w;
+            let mut tx = x;
+            for a in areas[i..j].iter() {
+                out.push((tx, y, a / rh, rh));
+                tx += a / rh;
+            }
+            y += rh;
+            h -= rh;
+        }
+        i = j;
+    }
+    out
+}
+
+impl<'a> Widget for Heatmap<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 { return; }
+        let theme = theme::current();
+        let header = Spans::from(vec![
+            Span::styled("Heatmap ", Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC)),
+            Span::raw(format!(" top {} symbols, area by 24h volume in USDT, colour by 24h change (full at ±{}%)",
+                              HEATMAP_SYMBOLS.min(self.infos.len()), FULL_CHANGE)),
+        ]);
+        buf.set_spans(area.x, area.y, &header, area.width);
+        let infos: Vec<&Info> = self.infos.iter().take(HEATMAP_SYMBOLS).collect();
+        let weights: Vec<f64> = infos.iter().map(|info| dec_to_f64(info.volume).max(0.0)).collect();
+        // terminal cells are about twice as high as wide, lay out in units of half a cell height
+        let (width, height) = (area.width as f64, (area.height - 1) as f64);
+        for (ix, (info, (x, y, w, h))) in infos.iter().zip(treemap(&weights, width, 2.0 * height)).enumerate() {
+            let x0 = area.x + x.round() as u16;
+            let x1 = area.x + (x + w).round() as u16;
+            let y0 = area.y + 1 + (y / 2.0).round() as u16;
+            let y1 = area.y + 1 + ((y + h) / 2.0).round() as u16;
+            if x1 <= x0 || y1 <= y0 { continue; }
+            let tile = Rect::new(x0, y0, x1 - x0, y1 - y0);
+            self.ref_hits.borrow_mut().push((tile, (ix, 0)));
+            let change = self.markets.get(&info.symbol).map(|mkt| 100.0 * dec_to_f64(mkt.change())).filter(|c| c.is_finite());
+            let bg = match change {
+                Some(c) if c > 0.0 => theme::shade(theme.up, 0.2 + 0.8 * (c / FULL_CHANGE).min(1.0)),
+                Some(c) if c < 0.0 => theme::shade(theme.down, 0.2 + 0.8 * (-c / FULL_CHANGE).min(1.0)),
+                _ => theme::shade(theme.muted, 0.3),
+            };
+            // leave a gap of one column between tiles
+            let fill = Rect::new(tile.x, tile.y, tile.width.saturating_sub(1).max(1), tile.height);
+            let style = Style::default().bg(bg).fg(theme::contrast(bg));
+            buf.set_style(fill, style);
+            let style = if info.symbol == *self.selected { style.add_modifier(Modifier::REVERSED) } else { style };
+            // labels if they fit
+            let symbol = info.short_symbol();
+            if symbol.len() as u16 <= fill.width {
+                buf.set_spans(fill.x, fill.y, &Spans::from(Span::styled(&**symbol, style.add_modifier(Modifier::BOLD))), fill.width);
+                let change = change.map(|c| format!("{:+.2}%", c)).unwrap_or_else(|| String::from("-"));
+                if fill.height > 1 && change.len() as u16 <= fill.width {
+                    buf.set_spans(fill.x, fill.y + 1, &Spans::from(Span::styled(change, style)), fill.width);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squarified() {
+        let tiles = treemap(&[6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0], 6.0, 4.0);
+        assert_eq!(tiles.len(), 7);
+        let area: f64 = tiles.iter().map(|(_, _, w, h)| w * h).sum();
+        assert!((area - 24.0).abs() < 1e-9);
+        // the first two tiles fill the left half as squares of about 3 x 2
+        assert!((tiles[0].2 - 3.0).abs() < 1e-9 && (tiles[0].3 - 2.0).abs() < 1e-9);
+        assert!((tiles[1].1 - 2.0).abs() < 1e-9);
+        for (x, y, w, h) in tiles.iter() {
+            assert!(*x >= -1e-9 && *y >= -1e-9 && x + w <= 6.0 + 1e-9 && y + h <= 4.0 + 1e-9);
+        }
+        assert!(treemap(&[], 6.0, 4.0).is_empty());
+    }
+}
@@ -1,35 +1,116 @@
-/// The help page
+/// The help overlay, generated from the key bindings and showing only those active in the current view
+use crate::keys::{Action, KeyMap};
+use crate::ui::UIView;
+use crate::theme;
 use tui::{
-    style::{Style, Color, Modifier},
-    widgets::{Block, Borders, Paragraph},
+    style::{Color, Style, Modifier},
+    widgets::{Block, Borders, Clear, Paragraph},
+    layout::{Rect},
     text::{Span, Spans},
+    backend::Backend,
+    terminal::Frame,
 };
 
-pub fn help<'a>() -> Paragraph<'a> {
-    let help: Vec<(&str, &str)> = vec!
-    [ ("h",    "Display help")
-    , ("l",    "Show price list")
-    , ("t",    "Show price table")
-    , ("g",    "Show graph at current time scale")
-    , ("0..9", "Show graph at time scale 0 to 9 (1m to 1d)")
-    , ("s",    "Select symbol")
-    , ("Home", "Set cursor to top left symbol (select symbol page)")
-    , ("%",    "Toggle percent/price display")
-    , ("x",    "Toggle extended/reduced view (Table display)")
-    , ("a",    "Display about page")
-    , ("Esc",  "Go back to previous view")
-    , ("q",    "Quit")
-    , ("C-c",  "Quit")
-    ];
-    let char_style = Style::default().add_modifier(Modifier::ITALIC).bg(Color::White).fg(Color::Black);
+/// What `view` shows
+fn view_description(view: UIView) -> &'static str {
+    match view {
+        UIView::PriceList   => "Live prices of all symbols, the selected symbol is highlighted",
+        UIView::PriceTable  => "Live prices by base (rows) and quote currency (columns), implied cross rates in italics",
+        UIView::Graph       => "Price chart of the selected symbol",
+        UIView::Search      => "Type to search symbols, the best match is at the top left",
+        UIView::Trades      => "Live trades of the selected symbol",
+        UIView::LargeTrades => "Trades above the large trade thresholds",
+        UIView::Detail      => "24h statistics of the selected symbol",
+        UIView::Filter      => "Rules deciding which symbols are shown",
+        UIView::Portfolio   => "Holdings from portfolio.toml valued live in USDT",
+        UIView::Heatmap     => "Symbols sized by 24h volume and coloured by 24h change",
+        UIView::Movers      => "Largest 24h gains and losses and highest volume, above a minimum volume",
+        UIView::Arbitrage   => "Round trips through three currencies, highest and lowest return after fees",
+        UIView::Empty | UIView::About => "Live cryptocurrency prices",
+    }
+}
+
+/// Does `action` do anything in `view`? Actions that switch views work everywhere.
+fn active(action: &Action, view: UIView) -> bool {
+    use UIView::*;
+    match action {
+        Action::Up | Action::Down                 => [PriceList, PriceTable, Search, Filter].contains(&view),
+        Action::Enter                             => [PriceList, PriceTable, Search, Filter, Heatmap].contains(&view),
+        Action::Left | Action::Right              => [PriceList, PriceTable, Search].contains(&view),
+        Action::Home | Action::PageUp | Action::PageDown => [PriceList, Search].contains(&view),
+        Action::Add | Action::Delete              => view == Filter,
+        Action::TogglePercent                     => [PriceList, PriceTable].contains(&view),
+        Action::ToggleExtended                    => view == PriceTable,
+        Action::Esc                               => view != PriceList,
+        _ => true,
+    }
+}
+
+/// Keys while text is entered, these don't go through the key bindings
+fn typing_help(view: UIView) -> Vec<(String, &'static str)> {
+    let lines: Vec<(&str, &'static str)> = match view {
+        UIView::Search => vec![
+            ("Type", "Search query"), ("Backspace", "Delete last character"), ("Arrows", "Move cursor"),
+            ("Home", "Set cursor to top left symbol"), ("PgUp, PgDn", "Scroll one page"),
+            ("Enter", "Show graph of selected symbol"), ("Esc", "Go back to previous view"),
+            ("M-<key>", "Command of <key> for the selected symbol, e.g. M-r for its trades"),
+        ],
+        _ => vec![
+            ("Type", "Rule, e.g. quote != BUSD or volume >= 1e6"), ("Backspace", "Delete last character"),
+            ("Enter", "Add rule"), ("Esc", "Cancel"),
+        ],
+    };
+    let mut lines: Vec<(String, &'static str)> = lines.into_iter().map(|(k, txt)| (String::from(k), txt)).collect();
+    lines.push((String::from("F1"), "Toggle this help"));
+    lines
+}
+
+/// What the mouse does in `view`
+fn mouse_help(view: UIView) -> Option<&'static str> {
+    match view {
+        UIView::PriceList | UIView::Search => Some("Click to select, click again for graph, wheel to scroll"),
+        UIView::PriceTable => Some("Click to select, click again for graph, wheel to move cursor"),
+        UIView::Heatmap    => Some("Click to select, click again for graph"),
+        UIView::Graph      => Some("Click to place the crosshair"),
+        _ => None,
+    }
+}
+
+/// The help lines of `view`, `typing` if text is being entered
+fn help(keys: &KeyMap, view: UIView, typing: bool) -> Vec<(String, &'static str)> {
+    let mut help = if typing { typing_help(view) } else { keys.help(|action| active(action, view)) };
+    if let Some(txt) = mouse_help(view) { help.push((String::from("Mouse"), txt)); }
+    help
+}
+
+/// Draw the help for `view` as a popup in the middle of `area`
+pub(super) fn draw_help<B: Backend>(f: &mut Frame<B>, area: Rect, keys: &KeyMap, view: UIView, typing: bool) {
+    let help = help(keys, view, typing);
+    let theme = theme::current();
+    let char_style = if theme.text == Color::Reset && theme.background == Color::Reset {
+        // no colours, e.g. with NO_COLOR
+        Style::default().add_modifier(Modifier::ITALIC | Modifier::REVERSED)
+    } else {
+        Style::default().add_modifier(Modifier::ITALIC).bg(theme.text).fg(theme.background)
+    };
     let width: usize = help.iter().map(|tup| tup.0.len()).max().unwrap_or(4);
-    let text: Vec<tui::text::Spans> = help.iter().map(|(k, txt)| {
+    let mut text: Vec<Spans> = vec![
+        Spans::from(Span::styled(view_description(view), Style::default().add_modifier(Modifier::BOLD))),
+        Spans::from(Vec::new()),
+    ];
+    text.extend(help.into_iter().map(|(k, txt)| {
         Spans::from(vec![
             Span::styled(format!(" {:<width$} ", k, width=width),char_style),
-            Span::raw(format!("  {}", *txt)),
+            Span::raw(format!("  {}", txt)),
         ])
-    }).collect();
-    Paragraph::new(text)
-        .block(Block::default().title("Help").borders(Borders::ALL))
-        .style(Style::default().fg(Color::White).bg(Color::Black))
+    }));
+    let text_width = text.iter().map(|spans| spans.width()).max().unwrap_or(0) as u16;
+    let w = (text_width + 4).min(area.width);
+    let h = (text.len() as u16 + 2).min(area.height);
+    let popup = Rect::new(area.x + (area.width - w)/2, area.y + (area.height - h)/2, w, h);
+    let paragraph = Paragraph::new(text)
+        .block(Block::default().title("Help (Esc to close)").borders(Borders::ALL))
+        .style(Style::default().fg(theme.text).bg(theme.background));
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
 }
@@ -0,0 +1,114 @@
+//! Widget `LargeTrades` and the `LargeTradeDetector` that flags trades with a large notional value
+use crate::utils::*;
+use crate::theme;
+use crate::config::LargeTradesConfig;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::{Rect},
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+use std::collections::{HashMap, VecDeque};
+use chrono::{Local, TimeZone};
+use dec::Decimal64;
+
+/// Maximum number of large trades that are remembered
+const MAX_LARGE_TRADES: usize = 1000;
+
+/// A trade whose notional value exceeded the threshold
+pub struct LargeTrade {
+    pub trade: Trade,
+    pub usdt: Decimal64,    // notional value in USDT
+}
+
+/// Flags trades with a notional value (in USDT) above a global or per symbol threshold
+pub struct LargeTradeDetector {
+    threshold: Decimal64,
+    thresholds: HashMap<Symbol, Decimal64>,
+    trades: VecDeque<LargeTrade>,   // newest at the back
+}
+
+impl LargeTradeDetector {
+    /// New `LargeTradeDetector` with the thresholds from `config`
+    pub fn new(config: &LargeTradesConfig) -> Self {
+        LargeTradeDetector {
+            threshold: f64_to_dec(config.threshold),
+            thresholds: config.thresholds.iter().map(|(s, t)| (Symbol::from(s.as_str()), f64_to_dec(*t))).collect(),
+            trades: VecDeque::new(),
+        }
+    }
+    /// Threshold in USDT for `symbol`
+    pub fn threshold(&self, symbol: &Symbol) -> Decimal64 {
+        *self.thresholds.get(symbol).unwrap_or(&self.threshold)
+    }
+    /// Symbols with their own threshold
+    pub fn symbols(&self) -> impl Iterator<Item=&Symbol> {
+        self.thresholds.keys()
+    }
+    /// Remember `trade` if its notional value `usdt` is at or above the threshold and return it
+    pub fn check(&mut self, trade: Trade, usdt: Decimal64) -> Option<&LargeTrade> {
+        let threshold = self.threshold(&trade.symbol);
+        if !threshold.is_positive() || usdt < threshold { return None; }
+        if self.trades.len() >= MAX_LARGE_TRADES { self.trades.pop_front(); }
+        self.trades.push_back(LargeTrade { trade, usdt });
+        self.trades.back()
+    }
+    /// Iterate over the large trades, newest first
+    pub fn iter(&self) -> impl Iterator<Item=&LargeTrade> {
+        self.trades.iter().rev()
+    }
+}
+
+impl LargeTrade {
+    /// One line summary for the message bar
+    pub fn summary(&self) -> String {
+        format!("Large trade {} {} {} @ {} = {:.0} USDT", self.trade.symbol, if self.trade.buy {"buy"} else {"sell"},
+                fmt_dec(self.trade.qty), fmt_dec(self.trade.px), dec_to_f64(self.usdt))
+    }
+}
+
+/// Widget LargeTrades
+///
+/// Rolling list of large trades of all monitored symbols, newest first.
+pub struct LargeTrades<'a> {
+    detector: &'a LargeTradeDetector,
+    n_symbols: usize,       // number of monitored symbols
+}
+
+impl<'a> LargeTrades<'a> {
+    pub fn new(detector: &'a LargeTradeDetector, n_symbols: usize) -> LargeTrades<'a> {
+        LargeTrades { detector, n_symbols }
+    }
+}
+
+impl<'a> Widget for LargeTrades<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 { return; }
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let header = Spans::from(vec![
+            Span::styled("Large trades ", bold.add_modifier(Modifier::ITALIC)),
+            Span::raw(format!(" >= {} USDT, {} symbols monitored", fmt_dec(self.detector.threshold), self.n_symbols)),
+        ]);
+        buf.set_spans(area.x, area.y, &header, area.width);
+        let columns = Spans::from(vec![
+            Span::styled(format!("{:<12}  {:<12} {:<4}  {:>16}  {:>16}  {:>14}", "Time", "Symbol", "Side", "Price", "Quantity", "USDT"), bold)
+        ]);
+        buf.set_spans(area.x, area.y+1, &columns, area.width);
+        for (y, large) in self.detector.iter().take(area.height as usize - 2).enumerate() {
+            let trade = &large.trade;
+            let t = Local.timestamp_millis_opt(trade.ts as i64).single()
+                         .map(|t| t.format("%H:%M:%S%.3f").to_string())
+                         .unwrap_or_default();
+            let (side, style) = if trade.buy { ("buy", theme::current().up_style()) }
+                                else         { ("sell", theme::current().down_style()) };
+            let spans = Spans::from(vec![
+                Span::raw(format!("{:<12}  ", t)),
+                Span::styled(format!("{:<12} ", trade.symbol), bold),
+                Span::styled(format!("{:<4}  {:>16}  {:>16}  {:>14.0}", side, fmt_dec(trade.px), fmt_dec(trade.qty),
+                                     dec_to_f64(large.usdt)), style),
+            ]);
+            buf.set_spans(area.x, area.y + y as u16 + 2, &spans, area.width);
+        }
+    }
+}
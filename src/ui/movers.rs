@@ -0,0 +1,97 @@
+//! Widget `Movers` with the top gainers, losers and most active symbols
+use crate::utils::*;
+use crate::ui::MarketState;
+use crate::theme;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::Rect,
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+use std::collections::HashMap;
+
+/// Widget Movers
+///
+/// Three panels: largest 24h gain, largest 24h loss and highest 24h volume in USDT, each limited to
+/// the symbols with at least `min_volume`.
+pub struct Movers<'a> {
+    infos: &'a [Info],                          // sorted list of `Info`
+    markets: &'a HashMap<Symbol, MarketState>,  // map symbol to `MarketState`
+    count: usize,                               // number of symbols per panel
+    min_volume: f64,                            // minimum 24h volume in USDT
+}
+
+/// A symbol with its 24h change in % and 24h volume in USDT
+struct Mover<'a> {
+    info: &'a Info,
+    mkt: &'a MarketState,
+    change: f64,
+    volume: f64,
+}
+
+/// Compact volume, e.g. `12.3M`
+fn fmt_volume(v: f64) -> String {
+    if v >= 1e9 { format!("{:.2}B", v / 1e9) }
+    else if v >= 1e6 { format!("{:.1}M", v / 1e6) }
+    else if v >= 1e3 { format!("{:.1}K", v / 1e3) }
+    else { format!("{:.0}", v) }
+}
+
+impl<'a> Movers<'a> {
+    pub fn new(infos: &'a [Info], markets: &'a HashMap<Symbol, MarketState>, count: usize, min_volume: f64) -> Movers<'a> {
+        Movers { infos, markets, count, min_volume }
+    }
+    /// Symbols with a 24h change and enough volume, the volume is live if it can be converted to USDT
+    fn movers(&self) -> Vec<Mover<'a>> {
+        let markets = self.markets;
+        let price = |s: &Symbol| markets.get(s).map(|m| m.px);
+        self.infos.iter().filter_map(|info| {
+            let mkt = markets.get(&info.symbol)?;
+            let change = 100.0 * dec_to_f64(mkt.change());
+            let volume = dec_to_f64(to_usdt(mkt.quote_volume, &info.quote, price).unwrap_or(info.volume));
+            if !change.is_finite() || volume.is_nan() || volume < self.min_volume { return None; }
+            Some(Mover { info, mkt, change, volume })
+        }).collect()
+    }
+    /// Draw the panel `title` with `movers` at `x`, `width` wide
+    fn render_panel(&self, title: &str, movers: &[&Mover], x: u16, width: u16, area: Rect, buf: &mut Buffer) {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        buf.set_spans(x, area.y + 1, &Spans::from(Span::styled(title, bold.add_modifier(Modifier::ITALIC))), width);
+        let columns = format!("{:<10} {:>12} {:>8} {:>9}", "Symbol", "Price", "Change", "Volume");
+        buf.set_spans(x, area.y + 2, &Spans::from(Span::styled(columns, bold)), width);
+        for (y, mover) in movers.iter().take(area.height as usize - 3).enumerate() {
+            let spans = Spans::from(vec![
+                Span::styled(format!("{:<10} ", mover.info.short_symbol()), bold),
+                Span::raw(format!("{:>12} ", mover.mkt.price_string())),
+                Span::styled(format!("{:>+7.2}% ", mover.change), mover.mkt.style_percent()),
+                Span::raw(format!("{:>9}", fmt_volume(mover.volume))),
+            ]);
+            buf.set_spans(x, area.y + y as u16 + 3, &spans, width);
+        }
+    }
+}
+
+impl<'a> Widget for Movers<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 4 { return; }
+        let movers = self.movers();
+        let header = Spans::from(vec![
+            Span::styled("Top movers ", Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC)),
+            Span::styled(format!(" {} symbols with 24h volume >= {} USDT", movers.len(), fmt_volume(self.min_volume)),
+                         theme::current().muted_style()),
+        ]);
+        buf.set_spans(area.x, area.y, &header, area.width);
+        let mut by_change: Vec<&Mover> = movers.iter().collect();
+        by_change.sort_by(|a, b| b.change.total_cmp(&a.change));
+        let gainers: Vec<&Mover> = by_change.iter().filter(|m| m.change > 0.0).take(self.count).cloned().collect();
+        let losers: Vec<&Mover> = by_change.iter().rev().filter(|m| m.change < 0.0).take(self.count).cloned().collect();
+        let mut active: Vec<&Mover> = movers.iter().collect();
+        active.sort_by(|a, b| b.volume.total_cmp(&a.volume));
+        active.truncate(self.count);
+        let width = area.width / 3;
+        self.render_panel("Gainers", &gainers, area.x, width.saturating_sub(2), area, buf);
+        self.render_panel("Losers", &losers, area.x + width, width.saturating_sub(2), area, buf);
+        self.render_panel("Most active", &active, area.x + 2 * width, width, area, buf);
+    }
+}
@@ -0,0 +1,95 @@
+//! Widget `Positions` with the live valuation of the portfolio
+use crate::utils::*;
+use crate::theme;
+use crate::portfolio::Position;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::{Rect},
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+
+/// Widget Positions
+///
+/// One line per holding, largest value first, with price and value in USDT, allocation and P&L,
+/// followed by the totals.
+pub struct Positions<'a> {
+    positions: &'a [Position],
+}
+
+/// Format an optional USDT amount
+fn fmt_usdt(x: Option<f64>, sign: bool) -> String {
+    match x {
+        Some(x) if sign => format!("{:+.2}", x),
+        Some(x) => format!("{:.2}", x),
+        None => String::from("-"),
+    }
+}
+
+/// Style of a profit or loss
+fn pnl_style(x: Option<f64>) -> Style {
+    let theme = theme::current();
+    match x {
+        Some(x) if x > 0.0 => theme.up_style(),
+        Some(x) if x < 0.0 => theme.down_style(),
+        Some(_) => Style::default(),
+        None => theme.muted_style(),
+    }
+}
+
+impl<'a> Positions<'a> {
+    pub fn new(positions: &'a [Position]) -> Positions<'a> {
+        Positions { positions }
+    }
+    /// Sum of `f` over all positions, ignoring those where it is `None`
+    fn total<F: Fn(&Position) -> Option<f64>>(&self, f: F) -> Option<f64> {
+        self.positions.iter().filter_map(f).fold(None, |sum, x| Some(sum.unwrap_or(0.0) + x))
+    }
+}
+
+impl<'a> Widget for Positions<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 { return; }
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        if self.positions.is_empty() {
+            let path = crate::portfolio::Portfolio::path().map(|p| format!("{:?}", p)).unwrap_or_default();
+            let spans = Spans::from(Span::styled(format!("No holdings, add them to {}", path), theme::current().muted_style()));
+            buf.set_spans(area.x, area.y, &spans, area.width);
+            return;
+        }
+        let total = self.total(|p| p.value());
+        let columns = Spans::from(vec![
+            Span::styled(format!("{:<8} {:>16} {:>14} {:>14} {:>7} {:>14} {:>14}",
+                                 "Asset", "Quantity", "Price", "Value", "Alloc", "24h P&L", "P&L"), bold)
+        ]);
+        buf.set_spans(area.x, area.y, &columns, area.width);
+        let mut positions: Vec<&Position> = self.positions.iter().collect();
+        positions.sort_by(|a, b| b.value().unwrap_or(-1.0).total_cmp(&a.value().unwrap_or(-1.0)));
+        let rows = (area.height as usize).saturating_sub(3);
+        for (y, position) in positions.iter().take(rows).enumerate() {
+            let value = position.value();
+            let alloc = match (value, total) {
+                (Some(value), Some(total)) if total > 0.0 => format!("{:.1}%", 100.0 * value / total),
+                _ => String::from("-"),
+            };
+            let px = position.px.map(|px| fmt_dec(f64_to_dec(px))).unwrap_or_else(|| String::from("no price"));
+            let spans = Spans::from(vec![
+                Span::styled(format!("{:<8} ", position.holding.asset), bold),
+                Span::raw(format!("{:>16} {:>14} {:>14} {:>7} ", position.holding.quantity, px, fmt_usdt(value, false), alloc)),
+                Span::styled(format!("{:>14} ", fmt_usdt(position.pnl_24h(), true)), pnl_style(position.pnl_24h())),
+                Span::styled(format!("{:>14}", fmt_usdt(position.pnl(), true)), pnl_style(position.pnl())),
+            ]);
+            buf.set_spans(area.x, area.y + 1 + y as u16, &spans, area.width);
+        }
+        let y = area.y + 2 + positions.len().min(rows) as u16;
+        let pnl_24h = self.total(|p| p.pnl_24h());
+        let pnl = self.total(|p| p.pnl());
+        let spans = Spans::from(vec![
+            Span::styled(format!("{:<8} {:>16} {:>14} {:>14} {:>7} ", "Total", "", "", fmt_usdt(total, false), ""), bold),
+            Span::styled(format!("{:>14} ", fmt_usdt(pnl_24h, true)), pnl_style(pnl_24h).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("{:>14}", fmt_usdt(pnl, true)), pnl_style(pnl).add_modifier(Modifier::BOLD)),
+        ]);
+        buf.set_spans(area.x, y, &spans, area.width);
+    }
+}
@@ -1,38 +1,110 @@
 ///! Widget `PriceList`
 use crate::utils::*;
-use crate::ui::MarketState;
+use crate::ui::{MarketState, Hits};
+use crate::theme;
+use crate::history::{Tick, TickHistory};
 use tui::{
-    style::{Style, Color, Modifier},
+    style::{Style, Modifier},
     widgets::{Widget},
     layout::{Rect},
     text::{Span, Spans},
     buffer::{Buffer}
 };
 use std::collections::HashMap;
+use dec::Decimal64;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Number of characters of a sparkline
+const SPARKLINE_WIDTH: usize = 8;
+/// Time span of a sparkline
+const SPARKLINE_MILLIS: u64 = 15 * 60 * 1000;
+/// Sparkline characters from low to high
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Sparkline of the prices `ticks` between `from` and `to` (millis), `width` characters wide, and
+/// whether the price rose. Each character is the last price of its time slot, empty slots repeat
+/// the previous one and those before the first tick are blank. `None` if there are no ticks.
+pub fn sparkline<'b, I>(ticks: I, from: u64, to: u64, width: usize) -> Option<(String, bool)>
+    where I: Iterator<Item=&'b Tick> {
+    let mut slots: Vec<Option<f64>> = vec![None; width];
+    let span = to.saturating_sub(from).max(1);
+    for tick in ticks.filter(|tick| tick.ts >= from && tick.ts <= to) {
+        let ix = ((tick.ts - from) as u128 * width as u128 / span as u128) as usize;
+        slots[ix.min(width - 1)] = Some(tick.px);
+    }
+    let first = slots.iter().position(|px| px.is_some())?;
+    for ix in first+1..width {
+        if slots[ix].is_none() { slots[ix] = slots[ix-1]; }
+    }
+    let pxs: Vec<f64> = slots[first..].iter().flatten().cloned().collect();
+    let min = pxs.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = pxs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let mut s: String = " ".repeat(first);
+    s.extend(pxs.iter().map(|px| {
+        if max > min { SPARKS[((px - min) / (max - min) * 7.0).round() as usize] } else { SPARKS[3] }
+    }));
+    Some((s, pxs[pxs.len()-1] >= pxs[0]))
+}
+
+/// How the prices are shown
+pub struct ListOptions<'a> {
+    pub show_percent: bool,                     // show % change instead of prices
+    pub conversion: Option<(&'a str, &'a HashMap<Symbol, Decimal64>)>, // display currency and rates of the quotes to it
+    pub history: Option<&'a TickHistory>,       // recent prices for the sparklines, none if `None`
+}
+
+/// What the price list reports back to the `UI` (interior mutability via Rc<RefCell<_>>)
+pub struct ListRefs {
+    pub shown: Rc<RefCell<(usize, usize)>>,     // height and number of symbols shown
+    pub hits: Rc<RefCell<Hits>>,                // clickable symbols and their index in `infos`
+}
 
 /// Widget PriceList
+/// 
+/// Symbols are shown in columns, starting with `infos[offset]`. 
 pub struct PriceList<'a> {
     infos: &'a Vec<Info>,                       // sorted list of `Info`
     markets: &'a HashMap<Symbol, MarketState>,  // map symbol to `MarketState`
-    show_percent: bool                          // flag indicating whether % change should be shown
+    show_percent: bool,                         // flag indicating whether % change should be shown
+    conversion: Option<(&'a str, &'a HashMap<Symbol, Decimal64>)>, // display currency and rates of the quotes to it
+    offset: usize,                              // index of first symbol shown
+    selected: &'a Symbol,                       // highlighted symbol
+    history: Option<&'a TickHistory>,           // recent prices for the sparklines, none if `None`
+    pub ref_shown: Rc<RefCell<(usize, usize)>>, // height and number of symbols shown (interior mutability via Rc<RefCell<_>>)
+    pub ref_hits: Rc<RefCell<Hits>>,            // clickable symbols and their index in `infos` (ditto)
 }
 
 impl<'a> PriceList<'a> {
-    pub fn new(infos: &'a Vec<Info>, markets: &'a HashMap<Symbol, MarketState>, show_percent: bool) -> PriceList<'a> {
-        PriceList {infos: infos, markets: markets, show_percent: show_percent }
+    /// Symbol without the quote if it is the display currency (or USDT if there is none)
+    fn short_symbol<'b>(&self, info: &'b Info) -> &'b Symbol {
+        info.short_symbol_in(self.conversion.map(|(currency, _)| currency).unwrap_or("USDT"))
+    }
+    pub fn new(infos: &'a Vec<Info>, markets: &'a HashMap<Symbol, MarketState>, options: ListOptions<'a>,
+               offset: usize, selected: &'a Symbol, refs: ListRefs) -> PriceList<'a> {
+        let ListOptions { show_percent, conversion, history } = options;
+        PriceList { infos, markets, show_percent, conversion, offset, selected, history,
+                    ref_shown: refs.shown, ref_hits: refs.hits }
     }
     fn render_info(self: &Self, info: &Info, width: usize) -> Spans<'a> {
-        let grey = Style::default().fg(Color::Gray);
+        let grey = theme::current().muted_style();
         let mkt = self.markets.get(&info.symbol);
-        let mut symbol = info.short_symbol().clone();
+        let mut symbol = self.short_symbol(info).clone();
         while symbol.len() < width { symbol.push(' ').unwrap_or(()); } // format! with {:<width$} does not work!
-        let symbol_span = Span::styled(format!("{} ",symbol), 
-                                        Style::default().add_modifier(Modifier::BOLD)
-                                                        .add_modifier(Modifier::ITALIC));
+        let mut symbol_style = Style::default().add_modifier(Modifier::BOLD).add_modifier(Modifier::ITALIC);
+        if info.symbol == *self.selected { symbol_style = symbol_style.add_modifier(Modifier::REVERSED); }
+        let symbol_span = Span::styled(format!("{} ",symbol), symbol_style);
         if self.show_percent {
             let percentage = mkt.map(|s| String::from(" ")+&s.percentage_string()).unwrap_or(String::from("-"));
             let percentage_span = Span::styled(percentage, mkt.map(|m| m.style_percent()).unwrap_or(grey));
             Spans::from(vec![symbol_span, percentage_span])
+        } else if let (Some((_, rates)), Some(m)) = (self.conversion, mkt) {
+            // prices that can't be converted are shown greyed out in their quote currency
+            let price_span = match rates.get(&info.quote) {
+                Some(rate) => Span::styled(fmt_dec(round_sig(m.px * *rate, 6)), m.style()),
+                None => Span::styled(format!("{} {}", m.price_string(), info.quote), grey.add_modifier(Modifier::ITALIC)),
+            };
+            Spans::from(vec![symbol_span, price_span])
         } else {
                 let px = mkt.map(|s| s.price_string()).unwrap_or(String::from("-"));
                 let price_span = Span::styled(px, mkt.map(|m| m.style()).unwrap_or(grey));
@@ -40,9 +112,24 @@ impl<'a> PriceList<'a> {
         }
     }
     fn render_infos(self: &Self, infos: &'a [Info]) -> (usize, Vec<Spans>) {
-        let width: usize = infos.iter().map(|i| i.short_symbol().len()).max().unwrap_or(0).max(8);
-        let spans = infos.iter().map(|info| self.render_info(info, width)).collect::<Vec<Spans>>();
-        let width = spans.iter().map(|t| t.width()).max().unwrap_or(0);
+        let width: usize = infos.iter().map(|i| self.short_symbol(i).len()).max().unwrap_or(0).max(8);
+        let mut spans = infos.iter().map(|info| self.render_info(info, width)).collect::<Vec<Spans>>();
+        let mut width = spans.iter().map(|t| t.width()).max().unwrap_or(0);
+        if let Some(history) = self.history {
+            // sparklines aligned after the prices
+            let to = now_timestamp();
+            let from = to.saturating_sub(SPARKLINE_MILLIS);
+            for (spans, info) in spans.iter_mut().zip(infos.iter()) {
+                let (s, style) = match sparkline(history.since(&info.symbol, from), from, to, SPARKLINE_WIDTH) {
+                    Some((s, true)) => (s, theme::current().up_style()),
+                    Some((s, false)) => (s, theme::current().down_style()),
+                    None => (String::new(), Style::default()),
+                };
+                spans.0.push(Span::raw(" ".repeat(width - spans.width() + 1)));
+                spans.0.push(Span::styled(s, style));
+            }
+            width += SPARKLINE_WIDTH + 1;
+        }
         (width, spans)
     }
 }
@@ -50,16 +137,36 @@ impl<'a> PriceList<'a> {
 impl<'a> Widget for PriceList<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let mut x: u16 = 0;
-        let mut counter: usize = 0;
+        let mut counter: usize = self.offset.min(self.infos.len());
         let height = area.height as usize;
+        if height == 0 { return; }
         while counter < self.infos.len() {
             let (width, spanss) = self.render_infos(&self.infos[counter..(counter+height).min(self.infos.len())]);
             if x + width as u16 >= area.width { break; }
+            let mut hits = self.ref_hits.borrow_mut();
             for (y, spans) in spanss.iter().enumerate() {
                 buf.set_spans(x, y as u16, spans, width as u16);
+                hits.push((Rect::new(x, y as u16, width as u16, 1), (counter + y, 0)));
             }
             x += width as u16 + 4;
             counter += spanss.len();
         }
+        *self.ref_shown.borrow_mut() = (height, counter - self.offset.min(self.infos.len()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparklines() {
+        let ticks: Vec<Tick> = [(10, 1.0), (30, 3.0), (35, 2.0), (70, 5.0)].iter()
+            .map(|(ts, px)| Tick { ts: *ts, px: *px }).collect();
+        assert_eq!(sparkline(ticks.iter(), 0, 80, 8), Some((String::from(" ▁▁▃▃▃▃█"), true)));
+        assert_eq!(sparkline(ticks[1..3].iter(), 0, 40, 4), Some((String::from("   ▄"), true)));
+        assert_eq!(sparkline(ticks[1..3].iter(), 0, 40, 8), Some((String::from("      █▁"), false)));
+        assert_eq!(sparkline(ticks[..1].iter(), 0, 80, 2), Some((String::from("▄▄"), true)));
+        assert_eq!(sparkline(ticks.iter(), 100, 200, 8), None);
     }
 }
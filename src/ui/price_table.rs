@@ -1,14 +1,37 @@
 ///! Widget `PriceList`
 use crate::utils::*;
-use crate::ui::MarketState;
+use crate::ui::{MarketState, Hits};
+use crate::theme;
 use tui::{
-    style::{Style, Color, Modifier},
+    style::{Style, Modifier},
     widgets::{Widget},
     layout::Rect,
     text::{Span, Spans},
     buffer::{Buffer}
 };
 use std::collections::HashMap;
+use std::cell::{RefCell, RefMut};
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Number of quote columns in the extended view
+const EXTENDED_COLUMNS: usize = 6;
+/// Number of quote columns in the reduced view
+const REDUCED_COLUMNS: usize = 4;
+
+/// How the prices are shown
+pub struct TableOptions<'a> {
+    pub show_percent: bool,                     // show % change instead of prices
+    pub extended: bool,                         // extended view vs. reduced
+    pub columns: &'a [String],                  // configured quote columns, the quotes with the most volume if empty
+}
+
+/// What the price table reports back to the `UI` (interior mutability via Rc<RefCell<_>>)
+pub struct TableRefs {
+    pub cursor: Rc<RefCell<(usize, usize)>>,    // index of selected base and quote
+    pub symbol: Rc<RefCell<Option<Symbol>>>,    // selected symbol, if it is traded
+    pub hits: Rc<RefCell<Hits>>,                // clickable cells and their base and quote index
+}
 
 /// Widget PriceList
 pub struct PriceTable<'a> {
@@ -16,19 +39,66 @@ pub struct PriceTable<'a> {
     markets: &'a HashMap<Symbol, MarketState>,  // map symbol to `MarketState`
     show_percent: bool,                         // flag indicating whether % change should be shown
     extended: bool,                             // flag indicating extended view vs. reduced
-    quotes: Vec<Symbol>,
+    quotes: Vec<Symbol>,                        // quote columns, configured or ranked by volume
     bases: Vec<Symbol>,
+    cross_rates: CrossRates<'a>,                // for the implied prices of the missing pairs
+    pub ref_cursor: Rc<RefCell<(usize, usize)>>,        // index of selected base and quote (interior mutability via Rc<RefCell<_>>)
+    pub ref_symbol: Rc<RefCell<Option<Symbol>>>,        // selected symbol, if it is traded (ditto)
+    pub ref_hits: Rc<RefCell<Hits>>,                    // clickable cells and their base and quote index (ditto)
 }
 
 impl<'a> PriceTable<'a> {
-    pub fn new(infos: &'a Vec<Info>, markets: &'a HashMap<Symbol, MarketState>, 
-               show_percent: bool, extended: bool) -> PriceTable<'a> {
-        let (bases ,quotes) = sort_base_quote(&infos);
-        PriceTable {infos: infos, markets: markets, show_percent: show_percent, extended: extended, 
-                    quotes: quotes, bases: bases }
+    pub fn new(infos: &'a Vec<Info>, markets: &'a HashMap<Symbol, MarketState>, options: TableOptions,
+               refs: TableRefs) -> PriceTable<'a> {
+        let TableOptions { show_percent, extended, columns } = options;
+        let TableRefs { cursor: ref_cursor, symbol: ref_symbol, hits: ref_hits } = refs;
+        let (bases, ranked) = sort_base_quote(infos);
+        let configured: Vec<Symbol> = columns.iter().filter_map(|q| {
+            let mut quote = Symbol::new();
+            quote.push_str(&q.to_uppercase()).ok()?;
+            Some(quote)
+        }).collect();
+        let quotes = if configured.is_empty() { ranked } else { configured };
+        let cross_rates = CrossRates::new(infos);
+        PriceTable { infos, markets, show_percent, extended, quotes, bases, cross_rates, ref_cursor, ref_symbol, ref_hits }
+    }
+    /// Quote currencies shown as columns
+    fn quote_columns(&self) -> &[Symbol] {
+        let n = if self.extended { EXTENDED_COLUMNS } else { REDUCED_COLUMNS };
+        &self.quotes[..n.min(self.quotes.len())]
+    }
+    /// Price or % change of `base` in `quote` implied by the markets of both against a common currency,
+    /// `None` if `base` and `quote` can't be converted
+    fn implied(&self, base: &str, quote: &str) -> Option<String> {
+        if base == quote { return None; }
+        let px = self.cross_rates.rate(base, quote, |s| self.markets.get(s).map(|m| m.px))?;
+        if self.show_percent {
+            let px_24h = self.cross_rates.rate(base, quote, |s| self.markets.get(s).map(|m| m.px_24h))?;
+            let p = dec_to_f64(px) / dec_to_f64(px_24h) - 1.0;
+            if !p.is_finite() { return None; }
+            let mut s = format!("{:+.2}", 100.0 * p);
+            s.truncate(6);
+            Some(format!("{:>6}", s))
+        } else {
+            Some(fmt_dec(round_sig(px, 6)))
+        }
+    }
+    /// Layout of the groups of columns that fit into `area`: x position and range of `bases` of each group
+    fn groups(&self, area: Rect, col_width: u16, n_quotes: usize) -> Vec<(u16, Range<usize>)> {
+        let height = area.height as usize;
+        let mut groups = Vec::new();
+        let mut x: u16 = 0;
+        let mut counter: usize = 0;
+        while height > 1 && x+col_width+2 <= area.width && counter < self.bases.len() {
+            let n = (height-1).min(self.bases.len()-counter);
+            groups.push((x, counter..counter+n));
+            counter += n;
+            x += (col_width+2) * (n_quotes as u16 + 1) + 3;    // base column, quote columns, gap
+        }
+        groups
     }
     fn render_info(self: &Self, info: &Info, width: usize) -> Spans<'a> {
-        let grey = Style::default().fg(Color::Gray);
+        let grey = theme::current().muted_style();
         let mkt = self.markets.get(&info.symbol);
         let symbol_span = Span::styled(format!("{:<width$} ", info.short_symbol(), width=width), 
                                         Style::default().add_modifier(Modifier::BOLD));
@@ -54,59 +124,105 @@ impl<'a> PriceTable<'a> {
 impl<'a> Widget for PriceTable<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let col_width = if self.show_percent {6} else {8};
-        let mut x: u16 = 0;
-        let height = area.height as usize;
-        let mut counter: usize = 0;
-        loop {
-            if x+col_width+2 > area.width { break; }
-            if counter >= self.bases.len() { break; }
-            let bases = &self.bases[counter..(counter+height-1).min(self.bases.len())];
-            counter += bases.len();
+        let quotes = self.quote_columns();
+        let groups = self.groups(area, col_width, quotes.len());
+        // keep the cursor on a visible cell
+        let n_bases = groups.last().map(|(_, range)| range.end).unwrap_or(0);
+        let mut cursor: RefMut<(usize, usize)> = self.ref_cursor.borrow_mut();
+        cursor.0 = cursor.0.min(n_bases.saturating_sub(1));
+        cursor.1 = cursor.1.min(quotes.len().saturating_sub(1));
+        *self.ref_symbol.borrow_mut() = self.bases.get(cursor.0).and_then(|base| {
+            let mut symbol = base.clone();
+            symbol.push_str(quotes.get(cursor.1)?).ok()?;
+            if self.markets.contains_key(&symbol) { Some(symbol) } else { None }
+        });
+        let selected = Modifier::REVERSED;
+        for (x, range) in groups.into_iter() {
+            let mut x = x;
+            let bases = &self.bases[range.clone()];
             // draw vertical header with base strings
             for (y,base) in bases.iter().enumerate() {
                 let span = Span::styled(String::from(&**base), Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC));
                 buf.set_spans(x, y as u16+1, &Spans::from(vec![span]), base.len() as u16);
             }
             x += col_width + 2;
-            let quotes = if self.extended {
-                vec!["USDT", "BTC", "EUR", "GBP", "BNB", "ETH"]     // extended view
-            } else {
-                vec!["USDT", "BTC", "BNB", "ETH"]                   // reduced view
-            };
             // columns
-            for quote in quotes.iter() {
+            for (i_quote, quote) in quotes.iter().enumerate() {
                 // header
-                let span = Span::styled(*quote, Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC));
+                let span = Span::styled(&**quote, Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC));
                 buf.set_spans(x, 0, &Spans::from(vec![span]), quote.len() as u16);
                 // prices
                 for (y,base) in bases.iter().enumerate() {
+                    self.ref_hits.borrow_mut().push((Rect::new(x, y as u16+1, col_width, 1), (range.start + y, i_quote)));
+                    let is_selected = (range.start + y, i_quote) == *cursor;
+                    // a configured quote can be too long for a `Symbol`, there is no market then
                     let mut symbol = base.clone();
-                    symbol.push_str(quote).unwrap(); // this should really be ok! If not, something weird is happening
-                    if let Some(mkt) = self.markets.get(&symbol) {
-                        if self.show_percent {
-                            let percentage = mkt.percentage_string();
-                            let perc_len = percentage.len() as u16;
-                            if x+perc_len < area.width {
-                                let percentage_span = Span::styled(percentage, mkt.style_percent());
-                                let spans = Spans::from(vec![percentage_span]);
-                                buf.set_spans(x, y as u16+1, &spans, perc_len);
-                            }
+                    let market = symbol.push_str(quote).ok().and_then(|_| self.markets.get(&symbol));
+                    if let Some(mkt) = market {
+                        let (s, style) = if self.show_percent {
+                            (mkt.percentage_string(), mkt.style_percent())
                         } else {
-                            let price = mkt.price_string();
-                            let price_len = price.len() as u16;
-                            if x+price_len < area.width {
-                                let price_span = Span::styled(price, mkt.style());
-                                let spans = Spans::from(vec![price_span]);
-                                buf.set_spans(x, y as u16+1, &spans, price_len);
-                            }
+                            (mkt.price_string(), mkt.style())
+                        };
+                        let len = s.len() as u16;
+                        if x+len < area.width {
+                            let style = if is_selected { style.add_modifier(selected) } else { style };
+                            let spans = Spans::from(vec![Span::styled(s, style)]);
+                            buf.set_spans(x, y as u16+1, &spans, len);
+                        }
+                    } else if let Some(s) = self.implied(base, quote) {
+                        // no market, show the cross rate in italics
+                        let len = s.len() as u16;
+                        if x+len < area.width {
+                            let style = theme::current().muted_style().add_modifier(Modifier::ITALIC);
+                            let style = if is_selected { style.add_modifier(selected) } else { style };
+                            let spans = Spans::from(vec![Span::styled(s, style)]);
+                            buf.set_spans(x, y as u16+1, &spans, len);
                         }
+                    } else if is_selected && x+1 < area.width {
+                        let spans = Spans::from(vec![Span::styled("-", Style::default().add_modifier(selected))]);
+                        buf.set_spans(x, y as u16+1, &spans, 1);
                     }
                 }
                 x += col_width+2;
                 if x >= area.width { break; }
             }
-            x += 3;
-            if x >= area.width { break; }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dec::Decimal64;
+
+    #[test]
+    fn partial_infos() {
+        // before the first tickers arrive the volumes are unknown
+        let infos: HashMap<Symbol, Info> = [("BTC", "USDT"), ("ETH", "BTC"), ("ETH", "USDT")].iter()
+            .map(|(base, quote)| Info { volume: Decimal64::NAN, ..Info::trading(base, quote, 0) })
+            .map(|info| (info.symbol.clone(), info)).collect();
+        let infos = trading_infos(&infos);
+        let markets = HashMap::new();
+        let refs = TableRefs { cursor: Rc::new(RefCell::new((0, 0))), symbol: Rc::new(RefCell::new(None)),
+                               hits: Rc::new(RefCell::new(Vec::new())) };
+        let table = PriceTable::new(&infos, &markets, TableOptions { show_percent: false, extended: true, columns: &[] }, refs);
+        let mut bases: Vec<&str> = table.bases.iter().map(|base| &**base).collect();
+        bases.sort_unstable();
+        assert_eq!(bases, vec!["BTC", "ETH"]);
+        let area = Rect::new(0, 0, 80, 10);
+        table.render(area, &mut Buffer::empty(area));
+    }
+
+    #[test]
+    fn long_quote() {
+        let infos = vec![Info::trading("BTC", "USDT", 1000)];
+        let markets = HashMap::new();
+        let refs = TableRefs { cursor: Rc::new(RefCell::new((0, 1))), symbol: Rc::new(RefCell::new(None)),
+                               hits: Rc::new(RefCell::new(Vec::new())) };
+        let columns = [String::from("usdt"), "X".repeat(inlinable_string::INLINE_STRING_CAPACITY)];
+        let table = PriceTable::new(&infos, &markets, TableOptions { show_percent: false, extended: true, columns: &columns }, refs);
+        let area = Rect::new(0, 0, 200, 10);
+        table.render(area, &mut Buffer::empty(area));
+    }
+}
@@ -1,9 +1,11 @@
 ///! Widget `Search`
 use crate::utils::*;
+use crate::ui::Hits;
+use crate::theme;
 use std::cell::{RefCell, RefMut};
 use std::rc::Rc;
 use tui::{
-    style::{Style, Color, Modifier},
+    style::{Style, Modifier},
     widgets::{Widget},
     layout::{Rect},
     text::{Span, Spans},
@@ -12,61 +14,87 @@ use tui::{
 
 
 /// Widget Search
+/// 
+/// The first line shows the search query, below is a grid of the matching symbols, best match first.
+/// The grid is filled column by column and scrolls horizontally so that the cursor is always visible.
 pub struct Search<'a> {
     symbol_width: usize,    // width of longest symbol in info
-    infos: &'a Vec<Info>,
+    infos: Vec<&'a Info>,   // matching symbols, best match first
+    query: &'a str,         // search query
     pub ref_i_symbol: Rc<RefCell<usize>>,    // index of selected symbol in infos (interior mutablity via Rc<RefCell<_>>)
     pub ref_cursor: Rc<RefCell<(u16, u16)>>,    // cursor position ix and iy (interior mutablity via Rc<RefCell<_>>)
+    pub ref_scroll: Rc<RefCell<(u16, u16)>>,    // first visible column and number of visible columns (ditto)
+    pub ref_hits: Rc<RefCell<Hits>>,            // clickable symbols and their cursor position (ditto)
 }
 
 impl<'a> Search<'a> {
-    pub fn new(infos: &'a Vec<Info>, ref_i_symbol: Rc<RefCell<usize>>, 
-               ref_cursor: Rc<RefCell<(u16,u16)>>) -> Search<'a> {
+    pub fn new(infos: Vec<&'a Info>, query: &'a str, ref_i_symbol: Rc<RefCell<usize>>, 
+               ref_cursor: Rc<RefCell<(u16,u16)>>, ref_scroll: Rc<RefCell<(u16,u16)>>,
+               ref_hits: Rc<RefCell<Hits>>) -> Search<'a> {
         let width = infos.iter().map(|info| info.symbol.len()).max().unwrap_or(8);
         Search { 
             symbol_width: width,
             infos: infos,
+            query,
             ref_i_symbol: ref_i_symbol,
             ref_cursor: ref_cursor,
+            ref_scroll,
+            ref_hits,
         }
     }
 }
 
 impl<'a> Widget for Search<'a> {
     fn render(self: Self, area: Rect, buf: &mut Buffer) {
-        let mut x: u16 = 0;   // x position in area
-        let mut y: u16 = 0;   // y position in area
-        let mut ix: u16 = 0;  // x position in table of symbols
-        let mut iy: u16 = 0;  // y position in table of symbols (always same as variable `y`, actually)
+        if area.height < 2 { return; }
+        let n = self.infos.len();
+        let height = area.height - 1;                                   // first line is the query
+        let col_width = self.symbol_width as u16 + 1;
+        let n_cols = n.div_ceil(height as usize).max(1) as u16;
+        let n_visible = ((area.width + 1) / col_width).max(1);          // number of visible columns
         let mut cursor: RefMut<(u16, u16)> = self.ref_cursor.borrow_mut();
-        let height = area.height as u16;
-        let width = area.width as u16;
-        let symbol_width = self.symbol_width as u16;
-        for (i_symbol, info) in self.infos.iter().enumerate() {
-            if y >= height { 
-                x += symbol_width + 1; 
-                if x >= width { break; }
-                y = 0; 
-                ix += 1;
-                iy = 0;
-            }
+        let mut scroll: RefMut<(u16, u16)> = self.ref_scroll.borrow_mut();
+        // keep cursor on a symbol
+        cursor.0 = cursor.0.min(n_cols - 1);
+        cursor.1 = cursor.1.min(height - 1);
+        let last_col_len = (n - (n_cols as usize - 1) * height as usize) as u16;
+        if cursor.0 == n_cols - 1 && last_col_len > 0 && cursor.1 >= last_col_len {
+            cursor.1 = last_col_len - 1;
+        }
+        // scroll so that the cursor is visible
+        let mut offset = scroll.0.min(n_cols.saturating_sub(n_visible));
+        if cursor.0 < offset { offset = cursor.0; }
+        if cursor.0 >= offset + n_visible { offset = cursor.0 + 1 - n_visible; }
+        *scroll = (offset, n_visible);
+        // query line with scroll indicator
+        let first = offset as usize * height as usize;
+        let last = ((offset + n_visible) as usize * height as usize).min(n);
+        let indicator = if n == 0 { String::from("no matches") } else {
+            format!("{}symbols {}–{} of {}{}", if offset > 0 {"◀ "} else {""}, first + 1, last, n,
+                    if last < n {" ▶"} else {""})
+        };
+        let query = Spans::from(vec![
+            Span::styled("Search: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(self.query),
+            Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+            Span::styled(format!("  {}", indicator), theme::current().muted_style()),
+        ]);
+        buf.set_spans(area.x, area.y, &query, area.width);
+        // the grid
+        for (i_symbol, info) in self.infos.iter().enumerate().skip(first).take(last - first) {
+            let ix = (i_symbol / height as usize) as u16;
+            let iy = (i_symbol % height as usize) as u16;
             let style: tui::style::Style = if ix==cursor.0 && iy==cursor.1 {
                 *self.ref_i_symbol.borrow_mut() = i_symbol;
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD).add_modifier(Modifier::ITALIC)
+                Style::default().fg(theme::current().cursor).add_modifier(Modifier::BOLD).add_modifier(Modifier::ITALIC)
             } else {
                 Style::default()
             };
             let span = Span::styled(format!("{}", info.symbol), style);
             let spans = Spans::from(vec![span]);
-            buf.set_spans(x, y as u16, &spans, symbol_width);
-            y += 1;
-            iy += 1;
-        }
-        if cursor.0 > ix {    // don't allow increasing cursor's ix beyond ix at end of for loop
-            cursor.0 = ix;
-        }
-        if cursor.1 >= height {    // don't allow increasing cursor's iy beyond height
-            cursor.1 = height - 1;
+            let x = area.x + (ix - offset) * col_width;
+            buf.set_spans(x, area.y + 1 + iy, &spans, col_width - 1);
+            self.ref_hits.borrow_mut().push((Rect::new(x, area.y + 1 + iy, col_width - 1, 1), (ix as usize, iy as usize)));
         }
     }
 }
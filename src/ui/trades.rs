@@ -0,0 +1,127 @@
+//! Widget `Trades` (time and sales) and the `TradeTape` that feeds it
+use crate::utils::*;
+use crate::theme;
+use tui::{
+    style::{Style, Modifier},
+    widgets::{Widget},
+    layout::{Rect},
+    text::{Span, Spans},
+    buffer::{Buffer}
+};
+use std::collections::VecDeque;
+use chrono::{Local, TimeZone};
+use dec::Decimal64;
+
+/// Maximum number of trades kept in the tape
+const TAPE_MAX_LEN: usize = 20000;
+/// Number of trades that are always kept, even if they are older than `STATS_WINDOW_MILLIS`
+const TAPE_MIN_LEN: usize = 500;
+/// Window for trades/s and volume statistics
+const STATS_WINDOW_MILLIS: u64 = 60_000;
+
+/// Most recent aggregated trades of a single symbol, newest at the back
+pub struct TradeTape {
+    pub symbol: Symbol,
+    trades: VecDeque<Trade>,
+}
+
+/// Statistics over the last `STATS_WINDOW_MILLIS`
+pub struct TapeStats {
+    pub trades_per_sec: f64,
+    pub buy_qty: Decimal64,
+    pub sell_qty: Decimal64,
+    pub buy_notional: Decimal64,
+    pub sell_notional: Decimal64,
+}
+
+impl TradeTape {
+    /// New empty `TradeTape` for `symbol`
+    pub fn new(symbol: Symbol) -> Self {
+        TradeTape { symbol, trades: VecDeque::with_capacity(TAPE_MIN_LEN) }
+    }
+    /// Append a trade, drop trades that are too old or too many
+    pub fn push(&mut self, trade: Trade) {
+        let ts = trade.ts;
+        self.trades.push_back(trade);
+        while self.trades.len() > TAPE_MAX_LEN
+            || (self.trades.len() > TAPE_MIN_LEN
+                && self.trades.front().map(|t| t.ts + STATS_WINDOW_MILLIS < ts).unwrap_or(false)) {
+            self.trades.pop_front();
+        }
+    }
+    /// Iterate over the trades, newest first
+    pub fn iter(&self) -> impl Iterator<Item=&Trade> {
+        self.trades.iter().rev()
+    }
+    /// Trades/s and buy/sell volumes over the last minute before `now` (millis)
+    pub fn stats(&self, now: u64) -> TapeStats {
+        let zero = Decimal64::from(0);
+        let mut stats = TapeStats { trades_per_sec: 0.0, buy_qty: zero, sell_qty: zero,
+                                    buy_notional: zero, sell_notional: zero };
+        let mut n: usize = 0;
+        for trade in self.iter().take_while(|t| t.ts + STATS_WINDOW_MILLIS >= now) {
+            n += 1;
+            if trade.buy {
+                stats.buy_qty += trade.qty;
+                stats.buy_notional += trade.notional();
+            } else {
+                stats.sell_qty += trade.qty;
+                stats.sell_notional += trade.notional();
+            }
+        }
+        stats.trades_per_sec = n as f64 / (STATS_WINDOW_MILLIS as f64 / 1000.0);
+        stats
+    }
+}
+
+/// Widget Trades
+///
+/// Header with statistics of the last minute followed by the most recent trades, newest first.
+pub struct Trades<'a> {
+    tape: &'a TradeTape,
+    info: Option<&'a Info>,     // used for labelling base and quote
+}
+
+impl<'a> Trades<'a> {
+    pub fn new(tape: &'a TradeTape, info: Option<&'a Info>) -> Trades<'a> {
+        Trades { tape, info }
+    }
+}
+
+impl<'a> Widget for Trades<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 { return; }
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let buy_style = theme::current().up_style();
+        let sell_style = theme::current().down_style();
+        let (base, quote) = match self.info {
+            Some(info) => (info.base.to_string(), info.quote.to_string()),
+            None => (String::new(), String::new()),
+        };
+        let stats = self.tape.stats(now_timestamp());
+        let header = Spans::from(vec![
+            Span::styled(format!("{} ", self.tape.symbol), bold.add_modifier(Modifier::ITALIC)),
+            Span::raw(format!(" {:.1} trades/s ", stats.trades_per_sec)),
+            Span::raw(" 1m buy "),
+            Span::styled(format!("{} {} ({} {})", fmt_dec(stats.buy_qty), base, fmt_dec(stats.buy_notional), quote), buy_style),
+            Span::raw("  sell "),
+            Span::styled(format!("{} {} ({} {})", fmt_dec(stats.sell_qty), base, fmt_dec(stats.sell_notional), quote), sell_style),
+        ]);
+        buf.set_spans(area.x, area.y, &header, area.width);
+        let columns = Spans::from(vec![
+            Span::styled(format!("{:<12}  {:>16}  {:>16}  {}", "Time", "Price", "Quantity", "Side"), bold)
+        ]);
+        buf.set_spans(area.x, area.y+1, &columns, area.width);
+        for (y, trade) in self.tape.iter().take(area.height as usize - 2).enumerate() {
+            let t = Local.timestamp_millis_opt(trade.ts as i64).single()
+                         .map(|t| t.format("%H:%M:%S%.3f").to_string())
+                         .unwrap_or_default();
+            let (side, style) = if trade.buy { ("buy", buy_style) } else { ("sell", sell_style) };
+            let spans = Spans::from(vec![
+                Span::raw(format!("{:<12}  ", t)),
+                Span::styled(format!("{:>16}  {:>16}  {}", fmt_dec(trade.px), fmt_dec(trade.qty), side), style),
+            ]);
+            buf.set_spans(area.x, area.y + y as u16 + 2, &spans, area.width);
+        }
+    }
+}
@@ -2,22 +2,24 @@
 //! websocket updates and klines obtained from Binance
 #![allow(dead_code)]
 
-use http_req::request;
-use serde::{Deserialize};
+use http_req::{request::Request, uri::Uri};
+use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use dec::Decimal64;
 use inlinable_string::{InlineString};
 
 /// Parse a String into a `Decimal64`, chop off superfluous zeros
-// todo: Make this return Result
 pub fn parse_dec(s: &String) -> Decimal64 {
-    if let Some(_) = s.find(".") {
-        s.trim_end_matches("0").parse().expect("parse_dec: Couldn't parse!")
-    } else {
-        s.parse().expect("parse_dec: Couldn't parse!")
-    }
+    try_parse_dec(s).expect("parse_dec: Couldn't parse!")
+}
+
+/// Parse a `&str` into a `Decimal64`, chop off superfluous zeros
+pub fn try_parse_dec(s: &str) -> Result<Decimal64, dec::ParseDecimalError> {
+    if s.contains('.') { s.trim_end_matches('0').parse() } else { s.parse() }
 }
 
 /// Nicely format a `Decimal64`
@@ -44,22 +46,51 @@ pub fn fmt_dec(d: Decimal64) -> String {
     }
 }
 
+/// Convert a `Decimal64` to `f64`, NAN if that is not possible
+pub fn dec_to_f64(d: Decimal64) -> f64 {
+    d.to_string().parse().unwrap_or(f64::NAN)
+}
+
+/// Convert a `f64` to `Decimal64`, NAN if that is not possible
+pub fn f64_to_dec(f: f64) -> Decimal64 {
+    f.to_string().parse().unwrap_or(Decimal64::NAN)
+}
+
+/// Round `d` to `digits` significant digits, e.g. for prices converted with a cross rate
+pub fn round_sig(d: Decimal64, digits: i32) -> Decimal64 {
+    let x = dec_to_f64(d);
+    if !x.is_finite() || x == 0.0 { return d; }
+    let decimals = (digits - 1 - x.abs().log10().floor() as i32).max(0) as usize;
+    format!("{:.*}", decimals, x).parse().unwrap_or(d)
+}
+
 /// String type for symbol
 pub type Symbol = InlineString;
 
-/// `Info` contains symbol, base, quote and precision
+/// `Info` contains symbol, base, quote, status and volume
 #[derive(Debug, Clone)]
 pub struct Info {
     pub symbol: Symbol,
     pub base: Symbol,
     pub quote: Symbol,
+    pub status: Symbol,     // e.g. TRADING, BREAK
     pub volume: Decimal64,
 }
 
 impl Info {
-    pub fn short_symbol(self: &Self) -> &InlineString {
-        if self.quote == "USDT" { &self.base }
-        else                { &self.symbol }
+    pub fn short_symbol(&self) -> &InlineString {
+        self.short_symbol_in("USDT")
+    }
+    /// The base if the quote is `currency`, the full symbol otherwise
+    pub fn short_symbol_in(&self, currency: &str) -> &InlineString {
+        if self.quote == currency { &self.base }
+        else                      { &self.symbol }
+    }
+    /// Trading symbol `<base><quote>` with `volume`, for tests
+    #[cfg(test)]
+    pub fn trading(base: &str, quote: &str, volume: i32) -> Info {
+        Info { symbol: Symbol::from(format!("{}{}", base, quote).as_str()), base: Symbol::from(base),
+               quote: Symbol::from(quote), status: Symbol::from("TRADING"), volume: Decimal64::from(volume) }
     }
 }
 
@@ -80,24 +111,65 @@ struct MarketInfoSymbol {
 
 }
 
-/// Get all traded binance symbols (unsorted)
-fn _get_infos() -> Result<HashMap<Symbol, Info>, Box<dyn std::error::Error>> {
-    let mut writer = Vec::with_capacity(3000000);   // exchangeInfo size is <2MB usually
-    if !request::get("https://api.binance.com/api/v3/exchangeInfo", &mut writer)?.status_code().is_success() {
-        return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "Req api/v3/exchangeInfo failed")));
+/// Timeout of a REST request, so that the thread of a blocking request that is no longer needed ends soon
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// GET `uri`, blocking for at most `HTTP_TIMEOUT`. `capacity` is the expected size of the response.
+pub fn http_get_blocking(uri: &str, capacity: usize) -> Result<Vec<u8>, String> {
+    let mut writer = Vec::with_capacity(capacity);
+    let parsed = Uri::try_from(uri).map_err(|e| format!("Bad uri {}: {}", uri, e))?;
+    let status = Request::new(&parsed)
+        .connect_timeout(Some(HTTP_TIMEOUT))
+        .read_timeout(Some(HTTP_TIMEOUT))
+        .write_timeout(Some(HTTP_TIMEOUT))
+        .timeout(HTTP_TIMEOUT)
+        .send(&mut writer)
+        .map_err(|e| format!("Req {} failed: {}", uri, e))?.status_code();
+    if !status.is_success() { return Err(format!("Req {} failed: {}", uri, status)); }
+    Ok(writer)
+}
+
+/// GET `uri` on a thread for blocking calls, so that the async tasks keep running meanwhile.
+/// `capacity` is the expected size of the response.
+pub async fn http_get(uri: String, capacity: usize) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let response = tokio::task::spawn_blocking(move || http_get_blocking(&uri, capacity)).await?;
+    Ok(response?)
+}
+
+/// Size and duration of a REST request including parsing, reported in the message bar
+#[derive(Debug, Clone, Copy)]
+pub struct Fetched {
+    pub bytes: usize,
+    pub millis: u64,
+}
+
+impl Fetched {
+    fn since(ts: u64, bytes: usize) -> Self {
+        Fetched { bytes, millis: now_timestamp().saturating_sub(ts) }
     }
+}
+
+impl std::fmt::Display for Fetched {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1} MB in {:.1}s", self.bytes as f64 / 1e6, self.millis as f64 / 1000.0)
+    }
+}
+
+/// Get all binance symbols (unsorted) from api/v3/exchangeInfo
+pub async fn get_symbols() -> Result<(HashMap<Symbol, Info>, Fetched), Box<dyn std::error::Error>> {
+    let ts = now_timestamp();
+    let writer = http_get(String::from("https://api.binance.com/api/v3/exchangeInfo"), 3000000).await?;   // exchangeInfo size is <2MB usually
     let cow = String::from_utf8_lossy(&writer);
     let market_info: MarketInfo = serde_json::from_str(cow.deref())?;
     let mut out = HashMap::<Symbol, Info>::new();
     for sym in market_info.symbols.iter() {
-        if sym.status == "TRADING" {
-            let symbol = InlineString::from(sym.symbol.as_str());
-            let base = InlineString::from(sym.base_asset.as_str());
-            let quote = InlineString::from(sym.quote_asset.as_str());
-            out.insert(symbol.clone(),  Info { symbol: symbol, base: base, quote: quote, volume: Decimal64::NAN});
-        }    
+        let symbol = InlineString::from(sym.symbol.as_str());
+        let base = InlineString::from(sym.base_asset.as_str());
+        let quote = InlineString::from(sym.quote_asset.as_str());
+        let status = InlineString::from(sym.status.as_str());
+        out.insert(symbol.clone(),  Info { symbol, base, quote, status, volume: Decimal64::NAN});
     } 
-    Ok(out)
+    Ok((out, Fetched::since(ts, writer.len())))
 }
 
 /// Market information subset as retrieved by API GET /api/v3/ticker/24hr
@@ -118,12 +190,10 @@ struct Ticker {
     last_price: String
 }
 
-/// Get all traded binance symbols
-pub fn get_markets<'a>() -> Result<HashMap<Symbol, Market>, Box<dyn std::error::Error>> {
-    let mut writer = Vec::with_capacity(1500000);   // 24hr size is <1MB usually
-    if !request::get("https://api.binance.com/api/v3/ticker/24hr", &mut writer)?.status_code().is_success() {
-        return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "Req api/v3/ticker/24hr failed")));
-    }
+/// Get all traded binance symbols from api/v3/ticker/24hr
+pub async fn get_markets() -> Result<(HashMap<Symbol, Market>, Fetched), Box<dyn std::error::Error>> {
+    let ts = now_timestamp();
+    let writer = http_get(String::from("https://api.binance.com/api/v3/ticker/24hr"), 1500000).await?;   // 24hr size is <1MB usually
     let cow = String::from_utf8_lossy(&writer);
     let tickers: Vec<Ticker> = serde_json::from_str(cow.deref())?;
     let mut out = HashMap::<Symbol, Market>::new();
@@ -137,31 +207,106 @@ pub fn get_markets<'a>() -> Result<HashMap<Symbol, Market>, Box<dyn std::error::
             out.insert(symbol, mkt);
         }
     }
-    Ok(out)
+    Ok((out, Fetched::since(ts, writer.len())))
+}
+
+/// Convert `value` denominated in `quote` to USDT via the `<quote>USDT` price given by `price`.
+///
+/// Returns `None` if there is no such price.
+pub fn to_usdt<F>(value: Decimal64, quote: &Symbol, price: F) -> Option<Decimal64>
+    where F: Fn(&Symbol) -> Option<Decimal64> {
+    if quote == "USDT" { return Some(value); }
+    let mut usdt_sym = quote.clone();
+    usdt_sym.push_str("USDT").ok()?;
+    price(&usdt_sym).map(|px| value * px)
+}
+
+/// Price of one `from` in `to`, from the `<from><to>` or the inverted `<to><from>` pair.
+///
+/// Missing, NaN and zero prices count as unavailable.
+pub fn direct_rate<F>(from: &str, to: &str, price: &F) -> Option<Decimal64>
+    where F: Fn(&Symbol) -> Option<Decimal64> {
+    if from == to { return Some(Decimal64::ONE); }
+    let ok = |px: Decimal64| px.is_finite() && !px.is_zero();
+    let mut symbol = Symbol::from(from);
+    symbol.push_str(to).ok()?;
+    if let Some(px) = price(&symbol).filter(|px| ok(*px)) { return Some(px); }
+    let mut symbol = Symbol::from(to);
+    symbol.push_str(from).ok()?;
+    price(&symbol).filter(|px| ok(*px)).map(|px| Decimal64::ONE / px)
+}
+
+/// Price of one `from` in `to`, directly or via a cross rate through one intermediate currency.
+///
+/// The intermediate currency is the other side of the most liquid pair of `from` that has a
+/// direct rate to `to`, `infos` must be sorted by volume.
+pub fn cross_rate<F>(from: &str, to: &str, infos: &[Info], price: F) -> Option<Decimal64>
+    where F: Fn(&Symbol) -> Option<Decimal64> {
+    let vias = infos.iter().filter_map(|info| {
+        if &*info.base == from { Some(&*info.quote) } else if &*info.quote == from { Some(&*info.base) } else { None }
+    });
+    cross_rate_via(from, to, vias, &price)
+}
+
+/// Price of one `from` in `to`, directly or through the first of `vias` that has direct rates
+fn cross_rate_via<'b, I, F>(from: &str, to: &str, mut vias: I, price: &F) -> Option<Decimal64>
+    where I: Iterator<Item=&'b str>, F: Fn(&Symbol) -> Option<Decimal64> {
+    if let Some(rate) = direct_rate(from, to, price) { return Some(rate); }
+    vias.find_map(|via| Some(direct_rate(from, via, price)? * direct_rate(via, to, price)?))
 }
 
-/// Get all traded binance symbols sorted by trading volume (in USDT)
+/// The currencies each currency is traded against, most liquid pair first, so that many cross rates
+/// can be looked up without scanning all `Info`s each time
+pub struct CrossRates<'a> {
+    pairs: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> CrossRates<'a> {
+    /// `infos` must be sorted by volume
+    pub fn new(infos: &'a [Info]) -> Self {
+        let mut pairs: HashMap<&'a str, Vec<&'a str>> = HashMap::new();
+        for info in infos {
+            pairs.entry(&*info.base).or_default().push(&*info.quote);
+            pairs.entry(&*info.quote).or_default().push(&*info.base);
+        }
+        CrossRates { pairs }
+    }
+    /// Same as `cross_rate`
+    pub fn rate<F>(&self, from: &str, to: &str, price: F) -> Option<Decimal64>
+        where F: Fn(&Symbol) -> Option<Decimal64> {
+        let vias = self.pairs.get(from).into_iter().flatten().cloned();
+        cross_rate_via(from, to, vias, &price)
+    }
+}
+
+/// Get all traded binance symbols with their trading volume (in USDT), fetching symbols and markets concurrently
 pub async fn get_infos() -> Result<Vec<Info>, String> {
-    let infos = _get_infos().map_err(|e| format!("Get infos failed: {:?}", e))?;
-    let markets = get_markets().map_err(|e| format!("Get markets failed: {:?}", e))?;
+    let (infos, markets) = tokio::join!(get_symbols(), get_markets());
+    let (infos, _) = infos.map_err(|e| format!("Get infos failed: {:?}", e))?;
+    let (markets, _) = markets.map_err(|e| format!("Get markets failed: {:?}", e))?;
+    Ok(traded_infos(infos, &markets))
+}
+
+/// The symbols that are trading, ordered by name, before their volumes are known
+pub fn trading_infos(infos: &HashMap<Symbol, Info>) -> Vec<Info> {
+    let mut out: Vec<Info> = infos.values().filter(|info| info.status == "TRADING").cloned().collect();
+    out.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    out
+}
+
+/// The symbols with a market, with the trading volume (in USDT) of the market
+pub fn traded_infos(infos: HashMap<Symbol, Info>, markets: &HashMap<Symbol, Market>) -> Vec<Info> {
     let mut out = Vec::<Info>::new();
     for (symbol, mut info) in infos.into_iter() {
         if let Some(market) = markets.get(&symbol) {
             // if the quote ccy is not USDT we try to convert the volume to USDT 
-            if info.quote != "USDT" {
-                let mut usdt_sym = info.quote.clone();
-                usdt_sym.push_str("USDT").map_err(|e| format!("{:?}", e))?;
-                if let Some(mkt2) = markets.get(&usdt_sym) {
-                    info.volume = market.volume * mkt2.price;
-                    out.push(info);
-                }
-            } else {
-                info.volume = market.volume;
+            if let Some(volume) = to_usdt(market.volume, &info.quote, |s| markets.get(s).map(|m| m.price)) {
+                info.volume = volume;
                 out.push(info);
             }
         }
     }
-    Ok(out)
+    out
 }
 
 #[tokio::test]
@@ -171,6 +316,24 @@ async fn test_get_infos() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_traded_infos() -> Result<(), Box<dyn std::error::Error>> {
+    let infos: HashMap<Symbol, Info> = vec![Info::trading("ETH", "BTC", 0), Info::trading("BTC", "USDT", 0),
+                                            Info { status: Symbol::from("BREAK"), ..Info::trading("LUNA", "USDT", 0) }]
+        .into_iter().map(|info| (info.symbol.clone(), info)).collect();
+    let partial: Vec<Symbol> = trading_infos(&infos).into_iter().map(|info| info.symbol).collect();
+    assert_eq!(partial, vec!["BTCUSDT", "ETHBTC"]);
+    let market = |px: &str, vol: &str| -> Result<Market, Box<dyn std::error::Error>> {
+        Ok(Market { price: px.parse()?, volume: vol.parse()?, price_change: Decimal64::ZERO })
+    };
+    let markets: HashMap<Symbol, Market> = vec![(Symbol::from("BTCUSDT"), market("20000", "1000")?),
+                                                (Symbol::from("ETHBTC"), market("0.05", "2")?)].into_iter().collect();
+    let infos = sort_infos(traded_infos(infos, &markets));
+    assert_eq!(infos.len(), 2);
+    assert_eq!((&*infos[0].symbol, infos[0].volume), ("ETHBTC", "40000".parse()?));
+    Ok(())
+}
+
 /// Sort [`Vec`] of [`Info`] by trading volume descending
 pub fn sort_infos(mut infos: Vec<Info>) -> Vec<Info> {
     infos.sort_by(|a, b| b.volume.partial_cmp(&a.volume).unwrap_or(std::cmp::Ordering::Equal));
@@ -182,27 +345,30 @@ pub fn infos_to_lookup(infos: &Vec<Info>) -> HashMap<Symbol, Info> {
     infos.iter().map(|item| (item.symbol.clone(), item.clone())).into_iter().collect()
 }
 
-/// Extract [`Vec`] of base strings and quote strings from [`Vec`] of [`Info`], sort by volume
+/// Extract [`Vec`] of base strings and quote strings from [`Vec`] of [`Info`], sort by volume.
+/// Unknown (NaN) volumes, e.g. of the partial infos, count as zero.
 pub fn sort_base_quote(infos: &Vec<Info>) -> (Vec<Symbol>, Vec<Symbol>) {
     let mut bases: HashMap<Symbol, Decimal64> = HashMap::new();
     let mut quotes: HashMap<Symbol, Decimal64> = HashMap::new();
     for info in infos.iter() {
         if info.base == "USDT" { continue; }
+        let volume = if info.volume.is_nan() { Decimal64::ZERO } else { info.volume };
         let vol = bases.entry(info.base.clone()).or_insert(Decimal64::from(0));
-        *vol = *vol+info.volume;
+        *vol += volume;
         let vol = quotes.entry(info.quote.clone()).or_insert(Decimal64::from(0));
-        *vol = *vol+info.volume;
+        *vol += volume;
     }
     let mut bases: Vec<(Symbol, &Decimal64)> = bases.iter().map(|(k,v)| (k.clone(),v)).collect();
-    bases.sort_by(|a,b| b.1.partial_cmp(a.1).unwrap());
+    bases.sort_by(|a,b| b.1.partial_cmp(a.1).unwrap_or(std::cmp::Ordering::Equal));
     let bases: Vec<Symbol> = bases.iter().map(|(k,_)| InlineString::from((*k).clone())).collect();
     let mut quotes: Vec<(Symbol, &Decimal64)> = quotes.iter().map(|(k,v)| (k.clone(),v)).collect();
-    quotes.sort_by(|a,b| b.1.partial_cmp(a.1).unwrap());
+    quotes.sort_by(|a,b| b.1.partial_cmp(a.1).unwrap_or(std::cmp::Ordering::Equal));
     let quotes: Vec<Symbol> = quotes.iter().map(|(k,_)| InlineString::from((*k).clone())).collect();
     (bases, quotes)
 }
 
 /// A single ohlcv bar 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Bar {
     pub t: u64, // open time
     pub o: f32,
@@ -215,7 +381,7 @@ pub struct Bar {
 /// Kline/Candlestick chart intervals.
 /// 
 /// See: https://binance-docs.github.io/apidocs/spot/en/#kline-candlestick-streams
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Interval {
     I1m, I3m, I5m, I15m, I30m, I1h, I2h, I4h, I6h, I8h, I12h, I1d, I3d, I1w, I1M
 }
@@ -302,15 +468,14 @@ fn parse_bar(bbar: &BinanceBar) -> Result<Bar, Box<dyn std::error::Error>> {
     })
 }
 
-/// Kline/candlestick bars for a symbol.
+/// Kline/candlestick bars for a symbol, the last 1000 or 1000 from open time `start`. Blocking, call it
+/// from a thread for blocking calls.
 ///  
 /// See: https://binance-docs.github.io/apidocs/spot/en/#kline-candlestick-data
-pub async fn get_klines(symbol: &Symbol, interval: &Interval) -> Result<Vec<Bar>, Box<dyn std::error::Error>> {
-    let uri = format!("https://api.binance.com/api/v3/klines?symbol={}&interval={}&limit=1000", symbol, interval);
-    let mut writer = Vec::with_capacity(200000);   // klines size is <100kB usually
-    if !request::get(uri, &mut writer)?.status_code().is_success() {
-        return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "Req api/v3/ticker/24hr failed")));
-    }
+pub fn get_klines(symbol: &Symbol, interval: &Interval, start: Option<u64>) -> Result<Vec<Bar>, Box<dyn std::error::Error>> {
+    let mut uri = format!("https://api.binance.com/api/v3/klines?symbol={}&interval={}&limit=1000", symbol, interval);
+    if let Some(start) = start { uri.push_str(&format!("&startTime={}", start)); }
+    let writer = http_get_blocking(&uri, 200000)?;   // klines size is <100kB usually
     let cow = String::from_utf8_lossy(&writer);
     let bars: Vec<BinanceBar> = serde_json::from_str(cow.deref())?;
     let mut out: Vec<Bar> = Vec::with_capacity(1000);
@@ -328,34 +493,111 @@ pub struct Update {
     pub ts: u64,           // timestamp (millis)
     pub px: Decimal64,     // price update
     pub px_24h: Decimal64, // price 24h ago
+    pub open: Decimal64,   // open price of the 24h window
+    pub high: Decimal64,   // 24h high
+    pub low: Decimal64,    // 24h low
+    pub volume: Decimal64, // 24h volume in base currency
+    pub quote_volume: Decimal64, // 24h volume in quote currency
+    pub n_trades: u64,     // 24h number of trades
+    pub bid: Decimal64,    // best bid price
+    pub ask: Decimal64,    // best ask price
 }
 
 /// A single update item from the markets websocket stream FOR DESER PURPOSES
+///
+/// The strings are borrowed from the message unless they contain escapes, the fields that are not needed
+/// are skipped.
 #[derive(Debug, Clone, Deserialize)]
-struct BinanceUpdate {
-    #[serde(alias = "E")]
+struct BinanceUpdate<'a> {
+    #[serde(rename = "E")]
     ts: u64,
-    #[serde(alias = "s")]
-    symbol: String,
-    #[serde(alias = "x")]
-    px_24h: String,
-    #[serde(alias = "c")]
-    px: String
+    #[serde(rename = "s", borrow)]
+    symbol: Cow<'a, str>,
+    #[serde(rename = "x", borrow)]
+    px_24h: Cow<'a, str>,
+    #[serde(rename = "c", borrow)]
+    px: Cow<'a, str>,
+    #[serde(rename = "o", borrow)]
+    open: Cow<'a, str>,
+    #[serde(rename = "h", borrow)]
+    high: Cow<'a, str>,
+    #[serde(rename = "l", borrow)]
+    low: Cow<'a, str>,
+    #[serde(rename = "v", borrow)]
+    volume: Cow<'a, str>,
+    #[serde(rename = "q", borrow)]
+    quote_volume: Cow<'a, str>,
+    #[serde(rename = "n")]
+    n_trades: u64,
+    #[serde(rename = "b", borrow)]
+    bid: Cow<'a, str>,
+    #[serde(rename = "a", borrow)]
+    ask: Cow<'a, str>,
+}
+
+impl<'a> BinanceUpdate<'a> {
+    fn to_update(&self) -> Result<Update, dec::ParseDecimalError> {
+        Ok(Update {
+            symbol: InlineString::from(&*self.symbol),
+            ts: self.ts,
+            px: try_parse_dec(&self.px)?,
+            px_24h: try_parse_dec(&self.px_24h)?,
+            open: try_parse_dec(&self.open)?,
+            high: try_parse_dec(&self.high)?,
+            low: try_parse_dec(&self.low)?,
+            volume: try_parse_dec(&self.volume)?,
+            quote_volume: try_parse_dec(&self.quote_volume)?,
+            n_trades: self.n_trades,
+            bid: try_parse_dec(&self.bid)?,
+            ask: try_parse_dec(&self.ask)?,
+        })
+    }
+}
+
+/// The updates of a ws stream message, each converted as soon as it is deserialised
+struct Updates(Vec<Update>);
+
+impl<'de> Deserialize<'de> for Updates {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UpdatesVisitor;
+        impl<'de> serde::de::Visitor<'de> for UpdatesVisitor {
+            type Value = Updates;
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("an array of 24hr tickers")
+            }
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Updates, A::Error> {
+                let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(update) = seq.next_element::<BinanceUpdate<'de>>()? {
+                    out.push(update.to_update().map_err(serde::de::Error::custom)?);
+                }
+                Ok(Updates(out))
+            }
+        }
+        deserializer.deserialize_seq(UpdatesVisitor)
+    }
 }
 
-/// Parse a ws stream message with updates (i.e. `Vec<BinanceUpdate>`)
+/// Parse a ws stream message with updates (i.e. an array of `BinanceUpdate`)
 ///
 /// See: https://binance-docs.github.io/apidocs/spot/en/#all-market-tickers-stream
-pub fn parse_updates<'a>(s: &String, out: &'a mut Vec::<Update>) -> Result<&'a Vec<Update>, Box<dyn std::error::Error>> {
-    let updates: Vec<BinanceUpdate> = serde_json::from_str(s.as_str())?;
-    for update in updates.iter() {
-        let ts = update.ts;
-        let symbol = InlineString::from(update.symbol.as_str());
-        let px_24h:Decimal64 = parse_dec(&update.px_24h);
-        let px:Decimal64 = parse_dec(&update.px);
-        out.push(Update{symbol: symbol, ts: ts as u64, px: px, px_24h: px_24h});
-    }
-    Ok(out)
+pub fn parse_updates(s: &str) -> Result<Vec<Update>, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_str::<Updates>(s)?.0)
+}
+
+#[test]
+fn test_parse_updates() -> Result<(), Box<dyn std::error::Error>> {
+    let msg = String::from(r#"[{"e":"24hrTicker","E":1672515782136,"s":"BNBBTC","p":"0.0015","P":"250.00","w":"0.0018","x":"0.0009","c":"0.0025","Q":"10","b":"0.0024","B":"10","a":"0.0026","A":"100","o":"0.0010","h":"0.0025","l":"0.0010","v":"10000","q":"18","O":0,"C":86400000,"F":0,"L":18150,"n":18151}]"#);
+    let updates = parse_updates(&msg)?;
+    assert_eq!(updates.len(), 1);
+    let u = &updates[0];
+    assert_eq!(u.px, "0.0025".parse::<Decimal64>()?);
+    assert_eq!(u.high, u.px);
+    assert_eq!(u.n_trades, 18151);
+    assert!(u.bid < u.ask);
+    // strings with escapes can't be borrowed
+    let escaped = parse_updates(&msg.replace(r#""c":"0.0025""#, r#""c":"0.002\u0035""#))?;
+    assert_eq!(escaped[0].px, u.px);
+    Ok(())
 }
 
 /// Get system timestamp in microseconds
@@ -363,3 +605,93 @@ pub fn now_timestamp() -> u64 {
     let ts = SystemTime::now();
     ts.duration_since(UNIX_EPOCH).expect("System clock is messed up!").as_millis() as u64
 }
+
+/// A single aggregated trade from the `<symbol>@aggTrade` websocket stream
+#[derive(Debug, Clone)]
+pub struct Trade {
+    pub symbol: Symbol,    // Exchange symbol
+    pub ts: u64,           // trade time (millis)
+    pub px: Decimal64,     // price
+    pub qty: Decimal64,    // quantity in base currency
+    pub buy: bool,         // true if the aggressor (taker) was the buyer
+}
+
+impl Trade {
+    /// Notional value of the trade in the quote currency
+    pub fn notional(&self) -> Decimal64 {
+        self.px * self.qty
+    }
+}
+
+/// A single aggregated trade FOR DESER PURPOSES
+#[derive(Debug, Clone, Deserialize)]
+struct BinanceAggTrade {
+    #[serde(alias = "T")]
+    ts: u64,
+    #[serde(alias = "s")]
+    symbol: String,
+    #[serde(alias = "p")]
+    px: String,
+    #[serde(alias = "q")]
+    qty: String,
+    #[serde(alias = "m")]
+    buyer_is_maker: bool,
+}
+
+/// Combined stream wrapper `{"stream": ..., "data": ...}` FOR DESER PURPOSES
+#[derive(Debug, Clone, Deserialize)]
+struct BinanceCombined<T> {
+    data: T
+}
+
+/// Parse an aggregated trade message, either raw or wrapped in a combined stream message
+///
+/// See: https://binance-docs.github.io/apidocs/spot/en/#aggregate-trade-streams
+pub fn parse_agg_trade(s: &str) -> Result<Trade, Box<dyn std::error::Error>> {
+    let trade: BinanceAggTrade = match serde_json::from_str::<BinanceCombined<BinanceAggTrade>>(s) {
+        Ok(combined) => combined.data,
+        Err(_) => serde_json::from_str(s)?,
+    };
+    Ok(Trade {
+        symbol: InlineString::from(trade.symbol.as_str()),
+        ts: trade.ts,
+        px: try_parse_dec(&trade.px)?,
+        qty: try_parse_dec(&trade.qty)?,
+        buy: !trade.buyer_is_maker,
+    })
+}
+
+#[test]
+fn test_parse_agg_trade() -> Result<(), Box<dyn std::error::Error>> {
+    let raw = r#"{"e":"aggTrade","E":1672515782136,"s":"BNBBTC","a":12345,"p":"0.00100000","q":"100.00000000","f":100,"l":105,"T":1672515782136,"m":true,"M":true}"#;
+    let trade = parse_agg_trade(raw)?;
+    assert_eq!(trade.symbol, "BNBBTC");
+    assert!(!trade.buy);
+    let combined = format!(r#"{{"stream":"bnbbtc@aggTrade","data":{}}}"#, raw.replace(r#""m":true"#, r#""m":false"#));
+    let trade = parse_agg_trade(&combined)?;
+    assert!(trade.buy);
+    assert_eq!(trade.notional(), "0.1".parse::<Decimal64>()?);
+    assert!(parse_agg_trade(&raw.replace("0.00100000", "0.001x")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cross_rate() -> Result<(), Box<dyn std::error::Error>> {
+    let infos = vec![Info::trading("BTC", "USDT", 0), Info::trading("ETH", "BTC", 0), Info::trading("EUR", "USDT", 0)];
+    let prices: HashMap<Symbol, Decimal64> = [("BTCUSDT", "20000"), ("ETHBTC", "0.05"), ("EURUSDT", "1.25")].iter()
+        .map(|(s, px)| (Symbol::from(*s), px.parse().unwrap())).collect();
+    let rate = |from: &str, to: &str| cross_rate(from, to, &infos, |s| prices.get(s).cloned());
+    assert_eq!(rate("BTC", "USDT"), Some("20000".parse()?));
+    assert_eq!(rate("USDT", "EUR"), Some("0.8".parse()?));
+    assert_eq!(rate("ETH", "USDT"), Some("1000.00".parse()?));
+    assert_eq!(rate("ETH", "ETH"), Some(Decimal64::ONE));
+    assert_eq!(rate("ETH", "EUR"), None);     // would need two intermediate currencies
+    assert_eq!(rate("DOGE", "USDT"), None);
+    let rates = CrossRates::new(&infos);
+    for (from, to) in [("BTC", "USDT"), ("USDT", "EUR"), ("ETH", "USDT"), ("ETH", "EUR"), ("DOGE", "USDT")].iter() {
+        assert_eq!(rates.rate(from, to, |s| prices.get(s).cloned()), rate(from, to));
+    }
+    assert_eq!(round_sig("1234.56789".parse()?, 6), "1234.57".parse()?);
+    assert_eq!(round_sig("0.000123456789".parse()?, 3), "0.000123".parse()?);
+    Ok(())
+}